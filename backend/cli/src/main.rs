@@ -0,0 +1,819 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{env, fs};
+
+use chrono::Utc;
+use clap::{Parser, Subcommand, ValueEnum};
+use similar::TextDiff;
+use todomd::formats::{github, nullboard, todotxt, trello};
+use todomd::history::{self, History};
+use todomd::json::JsonOptions;
+use todomd::render::{Html, Report};
+use todomd::{
+    Board, Edit, EditError, Field, Item, ItemId, ItemPath, ListPath, Operation, Outline, Query,
+    Span, UndoStack,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// How many changes `todomd undo` can go back
+const JOURNAL_LIMIT: usize = 20;
+
+/// Manage the tasks of a TODO.md
+#[derive(Parser)]
+#[command(name = "todomd", version)]
+struct Cli {
+    /// The file to work on. By default, the first TODO.md found in the current directory or one
+    /// of its parents.
+    #[arg(short, long, global = true)]
+    file: Option<PathBuf>,
+
+    /// Print the diff of a change without saving it
+    #[arg(short = 'n', long, global = true)]
+    dry_run: bool,
+
+    /// Where to keep the previous versions of the file. By default, `.history` next to it.
+    #[arg(long, global = true)]
+    history_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Items are given by path (like `1.0.2`), by id (like `^anchor`), or by a piece of their text
+/// that matches a single item. Lists are given by section title or by path (like `1.0`).
+#[derive(Subcommand)]
+enum Command {
+    /// List the items of every section, or of a single one
+    List {
+        section: Option<String>,
+        /// Only list the items that match a query, like `open and #backend and due < 2026-11-01`
+        #[arg(long = "where", value_name = "QUERY")]
+        query: Option<String>,
+    },
+    /// Show an item and its metadata
+    Show { item: String },
+    /// Add an item
+    Add {
+        text: String,
+        /// The section or list to add to. By default, the first list of the file.
+        #[arg(long)]
+        to: Option<String>,
+        /// Position in the list. By default, the item goes last.
+        #[arg(long)]
+        index: Option<usize>,
+        /// Add a plain list item, without a checkbox
+        #[arg(long)]
+        no_checkbox: bool,
+    },
+    /// Check an item, adding a checkbox if it has none
+    Done { item: String },
+    /// Uncheck an item
+    Undone { item: String },
+    /// Remove an item and its nested items
+    Rm { item: String },
+    /// Move an item and its nested items to another section or list
+    Mv {
+        item: String,
+        to: String,
+        /// Position in the target list, counted before the item is removed from its current
+        /// place. By default, the item goes last.
+        #[arg(long)]
+        index: Option<usize>,
+    },
+    /// Change the text or the metadata of an item
+    Edit {
+        item: String,
+        /// The new text of the item
+        text: Option<String>,
+        /// Set the due date, as YYYY-MM-DD, or remove it with `none`
+        #[arg(long)]
+        due: Option<String>,
+        /// Set the priority (lowest, low, medium, high or highest), or remove it with `none`
+        #[arg(long)]
+        priority: Option<String>,
+        /// Add a tag
+        #[arg(long)]
+        tag: Vec<String>,
+        /// Remove a tag
+        #[arg(long)]
+        untag: Vec<String>,
+    },
+    /// Print how the file is parsed
+    Dump {
+        /// Also print the source of each item and list
+        #[arg(short, long)]
+        verbose: bool,
+        /// Print the board as JSON, in the format of common/todomd/schema/board.schema.json
+        #[arg(long)]
+        json: bool,
+    },
+    /// Convert a file of another todo app to markdown, printed or written to a new file
+    Import {
+        input: PathBuf,
+        #[arg(long)]
+        from: Format,
+        /// Write the markdown to this file, which must not exist yet
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the board as a page or report, or in the format of another todo app
+    Export {
+        #[arg(value_enum)]
+        to: ExportFormat,
+        /// Write to this file instead, replacing it if it exists
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// For ics: the domain of the UIDs of the to-dos. By default, the name of the directory
+        /// of the file, so that the to-dos of different projects are told apart.
+        #[arg(long)]
+        uid_domain: Option<String>,
+    },
+    /// Undo the last change made with todomd, if the file wasn't changed since
+    Undo,
+    /// Make the last undone change again
+    Redo,
+    /// List or restore the versions of the file kept at each save
+    #[command(subcommand)]
+    History(HistoryCommand),
+}
+
+/// The formats of other todo apps
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// A board exported from nullboard.io, as JSON
+    Nullboard,
+    /// A todo.txt file, with a section for each project
+    TodoTxt,
+    /// A board exported from Trello, as JSON
+    Trello,
+    /// The JSON of `gh issue list --json ...` or `gh project item-list --format json`
+    Github,
+}
+
+/// The formats boards can be exported to
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// A self-contained HTML page with a column for each section
+    Html,
+    /// A markdown status report, with the progress of each section
+    Report,
+    /// An iCalendar file with a to-do for each item that has a due date
+    Ics,
+    /// A nullboard.io export, as JSON
+    Nullboard,
+    /// A todo.txt file, with the project of each section
+    TodoTxt,
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// List the saved versions, newest first
+    List,
+    /// Put the file back as it was in a saved version. The restore can be undone by restoring
+    /// the version before it.
+    Restore { id: String },
+}
+
+/// The directory todomd was run from. `just` runs recipes from the directory of the Justfile, so
+/// the Justfile passes the original one in `ORIGINAL_PWD`.
+fn working_dir() -> Result<PathBuf> {
+    match env::var_os("ORIGINAL_PWD") {
+        Some(dir) => Ok(dir.into()),
+        None => Ok(env::current_dir()?),
+    }
+}
+
+/// The first TODO.md in `dir` or one of its parents
+fn find_todo(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join("TODO.md"))
+        .find(|path| path.is_file())
+}
+
+/// A parsed TODO.md
+struct Todo {
+    /// The path to show in diffs and messages, relative to the working directory if possible
+    name: String,
+    source: String,
+    board: Board,
+    history: History,
+}
+
+impl Todo {
+    fn open(file: Option<&Path>, history_dir: Option<&Path>) -> Result<Self> {
+        let dir = working_dir()?;
+        let path = match file {
+            Some(file) => dir.join(file),
+            None => find_todo(&dir).ok_or_else(|| {
+                format!(
+                    "no TODO.md in {} or its parents, use --file to pick a file",
+                    dir.display()
+                )
+            })?,
+        };
+
+        let source =
+            fs::read_to_string(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+        let (board, _) = Board::parse(&source);
+
+        let name = path
+            .strip_prefix(&dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+
+        let mut history = History::new(&path);
+        if let Some(history_dir) = history_dir {
+            history = history.with_dir(dir.join(history_dir));
+        }
+
+        Ok(Todo {
+            name,
+            source,
+            board,
+            history,
+        })
+    }
+
+    fn print_diagnostics(&self) {
+        let (_, diagnostics) = Board::parse_with(&self.source, &self.board.options);
+
+        for diagnostic in &diagnostics {
+            let (line, col) = diagnostic.line_col(&self.source);
+            eprintln!("{}:{line}:{col}: {diagnostic}", self.name);
+        }
+    }
+
+    /// Finds an item by path, by id, or by a piece of its text
+    fn resolve(&self, item: &str) -> Result<ItemPath> {
+        let board = &self.board;
+
+        if let Ok(path) = item.parse::<ItemPath>() {
+            return match board.item(&path) {
+                Some(_) => Ok(path),
+                None => Err(format!("no item at {path}").into()),
+            };
+        }
+
+        if let Ok(id) = item.parse::<ItemId>()
+            && let Some(path) = board.find(&self.source, &id)
+        {
+            return Ok(path);
+        }
+
+        let needle = item.to_lowercase();
+        let matches: Vec<_> = board
+            .items()
+            .into_iter()
+            .filter(|(_, i)| i.text(&self.source).to_lowercase().contains(&needle))
+            .map(|(path, _)| path.to_string())
+            .collect();
+
+        match matches.as_slice() {
+            [path] => Ok(path.parse()?),
+            [] => Err(format!("no item matches {item:?}").into()),
+            _ => Err(format!("{item:?} matches items {}", matches.join(", ")).into()),
+        }
+    }
+
+    /// Finds a list by section title or by path. Without a target, the first list of the file.
+    fn target(&self, to: Option<&str>) -> Result<ListPath> {
+        let sections = &self.board.sections;
+
+        let Some(to) = to else {
+            let section = sections
+                .iter()
+                .position(|s| !s.element.lists.is_empty())
+                .unwrap_or(0);
+            return Ok(ListPath::new(section, 0));
+        };
+
+        if let Ok(path) = to.parse::<ListPath>() {
+            return Ok(path);
+        }
+
+        sections
+            .iter()
+            .position(|s| {
+                s.title(&self.source)
+                    .is_some_and(|title| title.trim().eq_ignore_ascii_case(to.trim()))
+            })
+            .map(|section| ListPath::new(section, 0))
+            .ok_or_else(|| format!("no section named {to:?}").into())
+    }
+
+    fn list_len(&self, list: &ListPath) -> usize {
+        self.board.list(list).map_or(0, |l| l.element.items.len())
+    }
+
+    /// Applies operations one after the other, prints the diff, and saves the result
+    fn apply(&self, operations: &[Operation], dry_run: bool) -> Result<()> {
+        let mut source = self.source.clone();
+        let mut board = Board::parse_with(&source, &self.board.options).0;
+
+        for operation in operations {
+            (source, board) = board.apply(&source, operation)?;
+        }
+
+        if source == self.source {
+            eprintln!("nothing to change");
+            return Ok(());
+        }
+
+        self.print_diff(&source);
+
+        if !dry_run {
+            // The whole command is undone at once
+            let mut journal = self.journal()?;
+            journal.record(&self.source, &Edit::between(&self.source, &source));
+            self.save(&source)?;
+            self.save_journal(&journal)?;
+        }
+
+        Ok(())
+    }
+
+    /// Undoes the last change in the journal, or redoes the last undone one
+    fn undo(&self, redo: bool, dry_run: bool) -> Result<()> {
+        let mut journal = self.journal()?;
+
+        let source = if redo {
+            journal.redo(&self.source)
+        } else {
+            journal.undo(&self.source)
+        };
+
+        let Some(source) = source else {
+            let (action, possible) = if redo {
+                ("redo", journal.can_redo())
+            } else {
+                ("undo", journal.can_undo())
+            };
+            return Err(if possible {
+                format!(
+                    "{} was changed since the last change to {action}",
+                    self.name
+                )
+                .into()
+            } else {
+                format!("nothing to {action}").into()
+            });
+        };
+
+        self.print_diff(&source);
+
+        if !dry_run {
+            self.save(&source)?;
+            self.save_journal(&journal)?;
+        }
+
+        Ok(())
+    }
+
+    fn print_diff(&self, source: &str) {
+        let (old_name, new_name) = if Path::new(&self.name).is_relative() {
+            (format!("a/{}", self.name), format!("b/{}", self.name))
+        } else {
+            (self.name.clone(), self.name.clone())
+        };
+        print!(
+            "{}",
+            TextDiff::from_lines(self.source.as_str(), source)
+                .unified_diff()
+                .header(&old_name, &new_name)
+        );
+    }
+
+    fn save(&self, source: &str) -> Result<()> {
+        self.history
+            .save(source)
+            .map_err(|e| format!("can't write {}: {e}", self.name).into())
+    }
+
+    /// The changes made with todomd, kept next to the versions of the file so that `todomd undo`
+    /// works across runs
+    fn journal_path(&self) -> PathBuf {
+        self.history.snapshots_dir().join("undo.json")
+    }
+
+    fn journal(&self) -> Result<UndoStack> {
+        let path = self.journal_path();
+
+        match fs::read_to_string(&path) {
+            Ok(json) => Ok(serde_json::from_str(&json)
+                .map_err(|e| format!("invalid undo journal {}: {e}", path.display()))?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(UndoStack::new().with_limit(JOURNAL_LIMIT))
+            }
+            Err(e) => Err(format!("can't read {}: {e}", path.display()).into()),
+        }
+    }
+
+    fn save_journal(&self, journal: &UndoStack) -> Result<()> {
+        let path = self.journal_path();
+        let write = || {
+            fs::create_dir_all(self.history.snapshots_dir())?;
+            fs::write(&path, serde_json::to_string(journal)?)
+        };
+
+        write().map_err(|e: io::Error| format!("can't write {}: {e}", path.display()).into())
+    }
+}
+
+fn checkbox(item: &Span<Item>) -> &'static str {
+    match item.element.checkbox.as_ref().map(|c| c.element) {
+        Some(true) => "[x] ",
+        Some(false) => "[ ] ",
+        None => "",
+    }
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parses a `--where` query, pointing at the column of errors
+fn parse_query(query: &str) -> Result<Query> {
+    Query::parse(query).map_err(|e| {
+        let caret = format!("{}^", " ".repeat(e.column - 1));
+        format!("invalid query: {e}\n  {query}\n  {caret}").into()
+    })
+}
+
+fn list(todo: &Todo, section: Option<&str>, query: Option<&Query>) -> Result<()> {
+    let source = &todo.source;
+    let mut items = todo.board.items();
+    let mut found = false;
+
+    if let Some(query) = query {
+        let matches: HashSet<_> = todo
+            .board
+            .query(source, query)
+            .into_iter()
+            .map(|m| m.path)
+            .collect();
+        items.retain(|(path, _)| matches.contains(path));
+    }
+
+    for (index, s) in todo.board.sections.iter().enumerate() {
+        let title = s.title(source);
+        if let Some(section) = section
+            && !title.is_some_and(|t| t.trim().eq_ignore_ascii_case(section.trim()))
+        {
+            continue;
+        }
+        found = true;
+
+        let section_items: Vec<_> = items
+            .iter()
+            .filter(|(p, _)| p.list.section == index)
+            .collect();
+        if query.is_some() && section_items.is_empty() {
+            continue;
+        }
+
+        if let Some(heading) = &s.element.heading {
+            println!(
+                "{} {}",
+                "#".repeat(heading.element.level as usize),
+                heading.title(source)
+            );
+        }
+
+        for (path, item) in section_items {
+            let indent = "  ".repeat(path.depth());
+            println!(
+                "{indent}{:<8} {}{}",
+                path.to_string(),
+                checkbox(item),
+                one_line(item.text(source))
+            );
+        }
+        println!();
+    }
+
+    match (section, found) {
+        (Some(section), false) => Err(format!("no section named {section:?}").into()),
+        _ => Ok(()),
+    }
+}
+
+fn show(todo: &Todo, item: &str) -> Result<()> {
+    let source = &todo.source;
+    let path = todo.resolve(item)?;
+    let item = todo.board.item(&path).expect("resolved items exist");
+
+    let line = source[..item.range.start].matches('\n').count() + 1;
+    println!("path:     {path}");
+    if let Some(id) = todo.board.item_id(source, &path) {
+        println!("id:       {id}");
+    }
+    println!("line:     {line}");
+    if let Some(checkbox) = &item.element.checkbox {
+        println!("checked:  {}", if checkbox.element { "yes" } else { "no" });
+    }
+    println!("text:     {}", one_line(item.text(source)));
+
+    for field in item.element.metadata.fields() {
+        let (name, value) = match field.element {
+            Field::Tag(tag) => ("tag", tag),
+            Field::Assignee(name) => ("assignee", name),
+            Field::Project(name) => ("project", name),
+            Field::Context(name) => ("context", name),
+            Field::Due(date) => ("due", date.to_string()),
+            Field::Created(date) => ("created", date.to_string()),
+            Field::Completed(date) => ("done", date.to_string()),
+            Field::Priority(priority) => ("priority", priority.to_string()),
+            Field::Other { key, value } => {
+                println!("{:<9} {value}", format!("{key}:"));
+                continue;
+            }
+        };
+        println!("{:<9} {value}", format!("{name}:"));
+    }
+
+    if let Some(nested) = &item.element.nested_list {
+        println!("nested:   {} items", nested.element.items.len());
+    }
+
+    Ok(())
+}
+
+fn history(todo: &Todo, command: HistoryCommand) -> Result<()> {
+    match command {
+        HistoryCommand::List => {
+            for snapshot in todo.history.list()?.iter().rev() {
+                let time = snapshot.time.format("%Y-%m-%d %H:%M:%S");
+                match &snapshot.restored_from {
+                    Some(from) => println!("{}  {time}  restored from {from}", snapshot.id),
+                    None => println!("{}  {time}", snapshot.id),
+                }
+            }
+            Ok(())
+        }
+        HistoryCommand::Restore { id } => {
+            todo.history
+                .restore(&id)
+                .map_err(|e| format!("can't restore {id}: {e}"))?;
+            eprintln!("restored {} as of {id}", todo.name);
+            Ok(())
+        }
+    }
+}
+
+fn import(input: &Path, from: Format, output: Option<&Path>) -> Result<()> {
+    let dir = working_dir()?;
+    let input = dir.join(input);
+    let contents =
+        fs::read_to_string(&input).map_err(|e| format!("can't read {}: {e}", input.display()))?;
+
+    let markdown = match from {
+        Format::Nullboard => nullboard::Export::from_json(&contents)
+            .map_err(|e| format!("invalid nullboard export {}: {e}", input.display()))?
+            .to_markdown(),
+        Format::TodoTxt => todotxt::to_markdown(&contents),
+        Format::Trello => trello::Export::from_json(&contents)
+            .map_err(|e| format!("invalid Trello export {}: {e}", input.display()))?
+            .to_markdown(),
+        Format::Github => github::Export::from_json(&contents)
+            .map_err(|e| format!("invalid gh output {}: {e}", input.display()))?
+            .to_markdown(),
+    };
+
+    match output {
+        Some(output) => {
+            let output = dir.join(output);
+            if output.exists() {
+                return Err(format!("{} already exists", output.display()).into());
+            }
+            history::write_atomically(&output, &markdown)
+                .map_err(|e| format!("can't write {}: {e}", output.display()))?;
+            Ok(())
+        }
+        None => {
+            print!("{markdown}");
+            Ok(())
+        }
+    }
+}
+
+fn export(
+    todo: &Todo,
+    to: ExportFormat,
+    output: Option<&Path>,
+    uid_domain: Option<String>,
+) -> Result<()> {
+    let exported = match to {
+        ExportFormat::Html => todo.board.render(&todo.source, &mut Html::new()),
+        ExportFormat::Report => todo.board.render(&todo.source, &mut Report::new()),
+        ExportFormat::Ics => {
+            let uid_domain = uid_domain.unwrap_or_else(|| {
+                let dir = todo.history.file().parent().and_then(Path::file_name);
+                dir.map_or("todomd".into(), |dir| dir.to_string_lossy().into_owned())
+            });
+            todo.board.to_ics(&todo.source, &uid_domain, Utc::now())
+        }
+        ExportFormat::Nullboard => format!("{}\n", todo.board.to_nullboard(&todo.source).to_json()),
+        ExportFormat::TodoTxt => {
+            // Read with the todo.txt grammar, so that `+project`, `@context` and `(A)` are fields
+            let (board, _) = Board::parse_with(&todo.source, &todotxt::parse_options());
+            board.to_todo_txt(&todo.source)
+        }
+    };
+
+    match output {
+        Some(output) => {
+            let output = working_dir()?.join(output);
+            history::write_atomically(&output, &exported)
+                .map_err(|e| format!("can't write {}: {e}", output.display()))?;
+        }
+        None => print!("{exported}"),
+    }
+    Ok(())
+}
+
+fn edit_operations(
+    item: ItemPath,
+    text: Option<String>,
+    due: Option<String>,
+    priority: Option<String>,
+    tags: Vec<String>,
+    untags: Vec<String>,
+) -> Result<Vec<Operation>> {
+    let mut operations = vec![];
+
+    if let Some(text) = text {
+        operations.push(Operation::Rename {
+            item: item.clone(),
+            text,
+        });
+    }
+    if let Some(due) = due {
+        let due = match due.as_str() {
+            "none" => None,
+            date => Some(
+                date.parse()
+                    .map_err(|e| format!("invalid date {date:?}: {e}"))?,
+            ),
+        };
+        operations.push(Operation::SetDue {
+            item: item.clone(),
+            due,
+        });
+    }
+    if let Some(priority) = priority {
+        let priority = match priority.as_str() {
+            "none" => None,
+            priority => Some(priority.parse()?),
+        };
+        operations.push(Operation::SetPriority {
+            item: item.clone(),
+            priority,
+        });
+    }
+    for tag in tags {
+        operations.push(Operation::AddTag {
+            item: item.clone(),
+            tag,
+        });
+    }
+    for tag in untags {
+        operations.push(Operation::RemoveTag {
+            item: item.clone(),
+            tag,
+        });
+    }
+
+    if operations.is_empty() {
+        return Err("nothing to edit, give a new text or a metadata option".into());
+    }
+
+    Ok(operations)
+}
+
+fn run(cli: Cli) -> Result<()> {
+    // Imports make a new file, instead of working on one
+    if let Command::Import {
+        input,
+        from,
+        output,
+    } = &cli.command
+    {
+        return import(input, *from, output.as_deref());
+    }
+
+    let todo = Todo::open(cli.file.as_deref(), cli.history_dir.as_deref())?;
+    let dry_run = cli.dry_run;
+
+    match cli.command {
+        Command::List { section, query } => {
+            let query = query.as_deref().map(parse_query).transpose()?;
+            todo.print_diagnostics();
+            list(&todo, section.as_deref(), query.as_ref())
+        }
+        Command::Show { item } => show(&todo, &item),
+        Command::Add {
+            text,
+            to,
+            index,
+            no_checkbox,
+        } => {
+            let list = todo.target(to.as_deref())?;
+            let operation = Operation::Insert {
+                index: index.unwrap_or_else(|| todo.list_len(&list)),
+                list,
+                text,
+                checkbox: (!no_checkbox).then_some(false),
+            };
+            todo.apply(&[operation], dry_run)
+        }
+        Command::Done { item } => {
+            let item = todo.resolve(&item)?;
+            let operation = match todo.board.set_checked(&todo.source, &item, true) {
+                Err(EditError::NoCheckbox(_)) => Operation::AddCheckbox {
+                    item,
+                    checked: true,
+                },
+                _ => Operation::SetChecked {
+                    item,
+                    checked: true,
+                },
+            };
+            todo.apply(&[operation], dry_run)
+        }
+        Command::Undone { item } => {
+            let item = todo.resolve(&item)?;
+            todo.apply(
+                &[Operation::SetChecked {
+                    item,
+                    checked: false,
+                }],
+                dry_run,
+            )
+        }
+        Command::Rm { item } => {
+            let item = todo.resolve(&item)?;
+            todo.apply(&[Operation::Delete { item }], dry_run)
+        }
+        Command::Mv { item, to, index } => {
+            let item = todo.resolve(&item)?;
+            let list = todo.target(Some(&to))?;
+            let operation = Operation::Move {
+                item,
+                index: index.unwrap_or_else(|| todo.list_len(&list)),
+                list,
+            };
+            todo.apply(&[operation], dry_run)
+        }
+        Command::Edit {
+            item,
+            text,
+            due,
+            priority,
+            tag,
+            untag,
+        } => {
+            let item = todo.resolve(&item)?;
+            let operations = edit_operations(item, text, due, priority, tag, untag)?;
+            todo.apply(&operations, dry_run)
+        }
+        Command::Dump { verbose, json } => {
+            todo.print_diagnostics();
+            if json {
+                let options = JsonOptions { text: verbose };
+                println!("{:#}", todo.board.to_json(&todo.source, &options));
+            } else {
+                print!(
+                    "{}",
+                    todo.board.render(&todo.source, &mut Outline::new(verbose))
+                );
+            }
+            Ok(())
+        }
+        Command::Import { .. } => unreachable!("imports are run before opening a file"),
+        Command::Export {
+            to,
+            output,
+            uid_domain,
+        } => export(&todo, to, output.as_deref(), uid_domain),
+        Command::Undo => todo.undo(false, dry_run),
+        Command::Redo => todo.undo(true, dry_run),
+        Command::History(command) => history(&todo, command),
+    }
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("todomd: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
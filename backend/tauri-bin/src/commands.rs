@@ -0,0 +1,386 @@
+//! Commands the frontend calls to load, edit and save a TODO.md.
+//!
+//! The open file lives in [`AppState`]. Edits are applied to the source in memory and only
+//! written to disk by [`save`], so the frontend can show unsaved changes. Changes made to the
+//! file by other programs are read by [`refresh`], which the file watcher calls. Unsaved edits
+//! are then applied again on top of the new file, and the ones whose items were changed there
+//! are shown as conflicts instead of overwriting those changes.
+//!
+//! Edits can be undone with [`undo`] and [`redo`], back to the last time the file was saved or
+//! read from disk: the edits before that are not in the operation log anymore, so they couldn't
+//! be applied again when the file changes.
+//!
+//! Each save keeps the previous and new versions of the file in its [`History`], which
+//! [`history_list`] and [`history_restore`] give access to.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::{fmt, fs};
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use todomd::history::{History, Snapshot};
+use todomd::json::JsonOptions;
+use todomd::{Board, Conflict, Diagnostic, EditError, Operation, OperationLog, UndoStack};
+
+use crate::watch;
+
+/// The file being edited
+struct OpenFile {
+    path: PathBuf,
+    source: String,
+    /// The source as it is on disk
+    saved: String,
+    board: Board,
+    diagnostics: Vec<Diagnostic>,
+    /// The unsaved edits
+    log: OperationLog,
+    /// Unsaved edits that couldn't be applied after the file changed on disk
+    conflicts: Vec<Conflict>,
+    /// The unsaved edits, to undo them
+    undo: UndoStack,
+}
+
+impl OpenFile {
+    fn read(path: PathBuf) -> Result<Self, CommandError> {
+        let source = fs::read_to_string(&path).map_err(|e| CommandError::io(&path, e))?;
+        let (board, diagnostics) = Board::parse(&source);
+        // The file watcher reports canonical paths
+        let path = path.canonicalize().unwrap_or(path);
+
+        Ok(OpenFile {
+            path,
+            saved: source.clone(),
+            source,
+            board,
+            diagnostics,
+            log: OperationLog::new(),
+            conflicts: vec![],
+            undo: UndoStack::new(),
+        })
+    }
+
+    /// Reads the file again after it changed on disk, and applies the unsaved edits on top of it.
+    /// Returns whether the board changed: our own saves, and changes that leave the file as it
+    /// was, are ignored.
+    fn refresh(&mut self) -> Result<bool, CommandError> {
+        let source = fs::read_to_string(&self.path).map_err(|e| CommandError::io(&self.path, e))?;
+
+        if source == self.saved {
+            return Ok(false);
+        }
+
+        let rebased = self.log.rebase(&source, &self.board.options);
+        self.saved = source;
+        self.source = rebased.source;
+        self.board = rebased.board;
+        self.diagnostics = rebased.diagnostics;
+        self.log = rebased.log;
+        self.conflicts.extend(rebased.conflicts);
+        // The edits were made again on another source
+        self.undo.clear();
+
+        Ok(true)
+    }
+
+    fn apply(&mut self, operation: &Operation) -> Result<(), CommandError> {
+        let edit = self
+            .board
+            .edit(&self.source, operation)
+            .map_err(CommandError::Edit)?;
+
+        // Edits that change nothing, like checking a checked item, are not undone: keeping them
+        // in the log would make undo take out the wrong operation
+        if edit.is_empty() {
+            return Ok(());
+        }
+
+        self.log.record(&self.board, &self.source, operation);
+        self.undo.record(&self.source, &edit);
+        self.set_source(edit.apply(&self.source));
+
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: String) {
+        let (board, diagnostics) = Board::parse_with(&source, &self.board.options);
+        self.source = source;
+        self.board = board;
+        self.diagnostics = diagnostics;
+    }
+
+    /// Undoes the last edit, or redoes the last undone one. Does nothing if there is none.
+    fn undo(&mut self, redo: bool) {
+        let source = if redo {
+            self.undo.redo(&self.source)
+        } else {
+            self.undo.undo(&self.source)
+        };
+
+        if let Some(source) = source {
+            if redo {
+                self.log.redo();
+            } else {
+                self.log.undo();
+            }
+            self.set_source(source);
+        }
+    }
+
+    fn history(&self) -> History {
+        History::new(&self.path)
+    }
+
+    fn view(&self) -> BoardView {
+        BoardView {
+            path: self.path.display().to_string(),
+            document: self
+                .board
+                .to_json(&self.source, &JsonOptions { text: true }),
+            source: self.source.clone(),
+            dirty: self.source != self.saved,
+            diagnostics: self.diagnostics.clone(),
+            conflicts: self.conflicts.iter().map(Conflict::to_string).collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AppState {
+    file: Mutex<Option<OpenFile>>,
+}
+
+impl AppState {
+    /// State with a file already open, so the frontend can start with [`get_board`]
+    pub fn open(path: PathBuf) -> Result<Self, CommandError> {
+        Ok(AppState {
+            file: Mutex::new(Some(OpenFile::read(path)?)),
+        })
+    }
+
+    /// The path of the open file
+    pub fn path(&self) -> Option<PathBuf> {
+        self.lock().as_ref().map(|file| file.path.clone())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<OpenFile>> {
+        // A panic while holding the lock leaves the state as it was before the command, which is
+        // still usable
+        self.file.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// What the frontend gets after each command
+#[derive(Serialize)]
+pub struct BoardView {
+    path: String,
+    source: String,
+    /// The board as a JSON document, see common/todomd/schema/board.schema.json
+    document: serde_json::Value,
+    /// Whether there are edits that were not saved yet
+    dirty: bool,
+    diagnostics: Vec<Diagnostic>,
+    /// Edits that were dropped because the file changed under them
+    conflicts: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum CommandError {
+    /// No file was opened yet
+    NoBoard,
+    Io {
+        path: String,
+        message: String,
+    },
+    Edit(EditError),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NoBoard => write!(f, "no file is open"),
+            CommandError::Io { path, message } => write!(f, "{path}: {message}"),
+            CommandError::Edit(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl CommandError {
+    fn io(path: &Path, error: std::io::Error) -> Self {
+        CommandError::Io {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Reads the open file again if `path` is the file and it changed on disk, for the file watcher
+pub fn refresh(state: &AppState, path: &Path) -> Result<Option<BoardView>, CommandError> {
+    let mut guard = state.lock();
+
+    match guard.as_mut() {
+        Some(file) if file.path == path => Ok(file.refresh()?.then(|| file.view())),
+        _ => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub fn open_board(
+    path: PathBuf,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BoardView, CommandError> {
+    let file = OpenFile::read(path)?;
+    if let Err(e) = watch::watch(&app, &file.path) {
+        eprintln!("todomd: can't watch {}: {e}", file.path.display());
+    }
+    let view = file.view();
+    *state.lock() = Some(file);
+
+    Ok(view)
+}
+
+#[tauri::command]
+pub fn get_board(state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    state
+        .lock()
+        .as_ref()
+        .map(OpenFile::view)
+        .ok_or(CommandError::NoBoard)
+}
+
+/// Applies an operation to the source in memory. The file is left alone until [`save`].
+#[tauri::command]
+pub fn apply_edit(edit: Operation, state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    let mut guard = state.lock();
+    let file = guard.as_mut().ok_or(CommandError::NoBoard)?;
+    file.apply(&edit)?;
+
+    Ok(file.view())
+}
+
+#[tauri::command]
+pub fn save(state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    let mut guard = state.lock();
+    let file = guard.as_mut().ok_or(CommandError::NoBoard)?;
+
+    // The watcher may not have seen the latest change yet
+    if file.path.exists() {
+        file.refresh()?;
+    }
+    file.history()
+        .save(&file.source)
+        .map_err(|e| CommandError::io(&file.path, e))?;
+    file.saved = file.source.clone();
+    file.log.clear();
+    file.undo.clear();
+
+    Ok(file.view())
+}
+
+/// Undoes the last unsaved edit
+#[tauri::command]
+pub fn undo(state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    let mut guard = state.lock();
+    let file = guard.as_mut().ok_or(CommandError::NoBoard)?;
+    file.undo(false);
+
+    Ok(file.view())
+}
+
+/// Makes the last undone edit again
+#[tauri::command]
+pub fn redo(state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    let mut guard = state.lock();
+    let file = guard.as_mut().ok_or(CommandError::NoBoard)?;
+    file.undo(true);
+
+    Ok(file.view())
+}
+
+/// The saved versions of the open file, newest first
+#[tauri::command]
+pub fn history_list(state: State<'_, AppState>) -> Result<Vec<Snapshot>, CommandError> {
+    let guard = state.lock();
+    let file = guard.as_ref().ok_or(CommandError::NoBoard)?;
+
+    let mut snapshots = file
+        .history()
+        .list()
+        .map_err(|e| CommandError::io(&file.path, e))?;
+    snapshots.reverse();
+
+    Ok(snapshots)
+}
+
+/// Puts the file on disk back as it was in a saved version. Unsaved edits are applied again on
+/// top of it, as when another program changes the file.
+#[tauri::command]
+pub fn history_restore(id: String, state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    let mut guard = state.lock();
+    let file = guard.as_mut().ok_or(CommandError::NoBoard)?;
+
+    file.history()
+        .restore(&id)
+        .map_err(|e| CommandError::io(&file.path, e))?;
+    file.refresh()?;
+
+    Ok(file.view())
+}
+
+/// Forgets the edits that were dropped by a change on disk, once the user has seen them
+#[tauri::command]
+pub fn dismiss_conflicts(state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    let mut guard = state.lock();
+    let file = guard.as_mut().ok_or(CommandError::NoBoard)?;
+    file.conflicts.clear();
+
+    Ok(file.view())
+}
+
+/// Reads the file again from disk, dropping unsaved edits
+#[tauri::command]
+pub fn reload(state: State<'_, AppState>) -> Result<BoardView, CommandError> {
+    let mut guard = state.lock();
+    let path = guard.as_ref().ok_or(CommandError::NoBoard)?.path.clone();
+
+    let file = OpenFile::read(path)?;
+    let view = file.view();
+    *guard = Some(file);
+
+    Ok(view)
+}
+
+#[cfg(test)]
+mod tests {
+    use todomd::ItemPath;
+
+    use super::*;
+
+    #[test]
+    fn undo_after_an_edit_that_changes_nothing() {
+        let dir = std::env::temp_dir().join(format!("todomd-commands-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("TODO.md");
+        fs::write(&path, "- [ ] a\n- [ ] b\n").unwrap();
+
+        let mut file = OpenFile::read(path.clone()).unwrap();
+        let check = Operation::SetChecked {
+            item: ItemPath::new(0, 0, 0),
+            checked: true,
+        };
+        file.apply(&check).unwrap();
+        file.apply(&check).unwrap();
+        file.undo(false);
+        assert_eq!(file.source, "- [ ] a\n- [ ] b\n");
+
+        // The undone edit is not applied again on top of the changed file
+        fs::write(&path, "- [ ] a\n- [ ] b\n- [ ] c\n").unwrap();
+        assert!(file.refresh().unwrap());
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(file.source, "- [ ] a\n- [ ] b\n- [ ] c\n");
+        assert!(file.log.is_empty());
+    }
+}
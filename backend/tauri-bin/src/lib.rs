@@ -1,24 +1,47 @@
-use std::sync::OnceLock;
+use std::path::PathBuf;
 
-static NUM: OnceLock<u32> = OnceLock::new();
+mod commands;
+mod watch;
 
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
-
-#[tauri::command]
-fn get_num() -> u32 {
-    *NUM.get().unwrap_or(&8)
-}
+use commands::AppState;
+use tauri::Manager;
+use watch::FileWatcher;
 
+/// Runs the app, with `file` already open if given
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run(n: u32) {
-    NUM.set(n).unwrap();
+pub fn run(file: Option<PathBuf>) {
+    let state = match file {
+        Some(path) => AppState::open(path).unwrap_or_else(|e| {
+            eprintln!("todomd: {e}");
+            AppState::default()
+        }),
+        None => AppState::default(),
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet, get_num])
+        .manage(state)
+        .manage(FileWatcher::default())
+        .setup(|app| {
+            if let Some(path) = app.state::<AppState>().path()
+                && let Err(e) = watch::watch(app.handle(), &path)
+            {
+                eprintln!("todomd: can't watch {}: {e}", path.display());
+            }
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            commands::open_board,
+            commands::get_board,
+            commands::apply_edit,
+            commands::undo,
+            commands::redo,
+            commands::save,
+            commands::reload,
+            commands::dismiss_conflicts,
+            commands::history_list,
+            commands::history_restore,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -2,11 +2,25 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use std::env;
+use std::path::PathBuf;
+
+/// The file given on the command line, or the first TODO.md in the working directory or one of
+/// its parents. `just` runs recipes from the directory of the Justfile, so the Justfile passes
+/// the original working directory in `ORIGINAL_PWD`.
+fn todo_file() -> Option<PathBuf> {
+    let dir = env::var_os("ORIGINAL_PWD")
+        .map(PathBuf::from)
+        .or_else(|| env::current_dir().ok())?;
+
+    match env::args_os().nth(1) {
+        Some(file) => Some(dir.join(file)),
+        None => dir
+            .ancestors()
+            .map(|dir| dir.join("TODO.md"))
+            .find(|path| path.is_file()),
+    }
+}
 
 fn main() {
-    let n = env::args()
-        .nth(1)
-        .and_then(|s| s.parse::<u32>().ok())
-        .unwrap_or(8);
-    tauri_lib::run(n);
+    tauri_lib::run(todo_file());
 }
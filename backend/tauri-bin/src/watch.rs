@@ -0,0 +1,59 @@
+//! Watching the open file, so that changes made by other programs show up in the app.
+//!
+//! Editors often save in bursts (a temporary file, a rename, then a metadata change), so events
+//! are debounced and the file is read once they settle. The parent directory is watched rather
+//! than the file, since saving through a rename replaces the file that was watched.
+
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use notify_debouncer_mini::{DebounceEventResult, Debouncer, new_debouncer};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::commands::{self, AppState};
+
+/// The event sent to the frontend with the new [`commands::BoardView`] when the file changed
+pub const BOARD_CHANGED: &str = "board-changed";
+
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The watcher of the open file. Replacing it stops the previous one.
+#[derive(Default)]
+pub struct FileWatcher(Mutex<Option<Debouncer<RecommendedWatcher>>>);
+
+/// Starts watching `path`, instead of the file watched until now
+pub fn watch(app: &AppHandle, path: &Path) -> notify::Result<()> {
+    let watched = path.canonicalize()?;
+    let dir = watched.parent().unwrap_or(Path::new("/")).to_path_buf();
+
+    let handle = app.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+        Ok(events) if events.iter().any(|e| e.path == watched) => changed(&handle, &watched),
+        Ok(_) => {}
+        Err(e) => eprintln!("todomd: watching {}: {e}", watched.display()),
+    })?;
+    debouncer
+        .watcher()
+        .watch(&dir, RecursiveMode::NonRecursive)?;
+
+    let watcher = app.state::<FileWatcher>();
+    *watcher.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(debouncer);
+
+    Ok(())
+}
+
+fn changed(app: &AppHandle, path: &Path) {
+    let state = app.state::<AppState>();
+
+    match commands::refresh(&state, path) {
+        Ok(Some(view)) => {
+            if let Err(e) = app.emit(BOARD_CHANGED, view) {
+                eprintln!("todomd: {e}");
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("todomd: {e}"),
+    }
+}
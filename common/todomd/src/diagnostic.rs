@@ -0,0 +1,70 @@
+use std::fmt;
+
+use crate::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Something worth telling the user about the source, found while parsing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(range: Range, severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            range,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    pub fn info(range: Range, message: impl Into<String>) -> Self {
+        Self::new(range, Severity::Info, message)
+    }
+
+    pub fn warning(range: Range, message: impl Into<String>) -> Self {
+        Self::new(range, Severity::Warning, message)
+    }
+
+    pub fn error(range: Range, message: impl Into<String>) -> Self {
+        Self::new(range, Severity::Error, message)
+    }
+
+    /// Line and column where the diagnostic starts, both starting at 1. Columns count characters,
+    /// not bytes.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.range.start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let col = source[line_start..self.range.start].chars().count() + 1;
+
+        (line, col)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
@@ -0,0 +1,2168 @@
+//! Editing the markdown source through the board.
+//!
+//! Edits are never done by printing the board back to markdown. Instead, each operation is turned
+//! into an [`Edit`]: a few byte-range splices on the original source. Everything outside of the
+//! spliced ranges is kept byte for byte, so editing a file through todomd gives the same diff as
+//! editing it by hand.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::metadata::{self, Field, Priority};
+use crate::{Board, Item, ItemPath, List, ListKind, ListPath, Range, Span};
+
+/// Replaces `range` of the source with `text`. An empty range is an insertion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Splice {
+    pub range: Range,
+    pub text: String,
+}
+
+/// A set of non-overlapping splices, kept sorted by position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edit {
+    splices: Vec<Splice>,
+}
+
+impl Edit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A single splice that turns `old` into `new`, replacing the part between their common
+    /// prefix and suffix
+    pub fn between(old: &str, new: &str) -> Edit {
+        let mut prefix = old
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let mut suffix = old
+            .bytes()
+            .rev()
+            .zip(new.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix)
+        {
+            suffix -= 1;
+        }
+
+        let mut edit = Edit::new();
+        if old != new {
+            edit.splice(prefix..old.len() - suffix, &new[prefix..new.len() - suffix]);
+        }
+        edit
+    }
+
+    /// Adds a splice. Insertions at the same position are applied in the order they were added.
+    ///
+    /// Panics if `range` overlaps a splice that was already added.
+    pub fn splice(&mut self, range: Range, text: impl Into<String>) {
+        assert!(range.start <= range.end);
+        assert!(
+            self.splices
+                .iter()
+                .all(|s| !(range.start < s.range.end && s.range.start < range.end)),
+            "overlapping splice at {range:?}"
+        );
+
+        let index = self
+            .splices
+            .partition_point(|s| (s.range.start, s.range.end) <= (range.start, range.end));
+
+        self.splices.insert(
+            index,
+            Splice {
+                range,
+                text: text.into(),
+            },
+        );
+    }
+
+    pub fn splices(&self) -> &[Splice] {
+        &self.splices
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.splices.is_empty()
+    }
+
+    /// Applies the edit to the source it was computed from
+    pub fn apply(&self, source: &str) -> String {
+        let mut out = String::with_capacity(source.len());
+        let mut last = 0;
+
+        for splice in &self.splices {
+            out.push_str(&source[last..splice.range.start]);
+            out.push_str(&splice.text);
+            last = splice.range.end;
+        }
+
+        out.push_str(&source[last..]);
+
+        out
+    }
+
+    /// The edit that undoes this one: applied to the result of [`Edit::apply`] on `source`, it
+    /// gives back `source` byte for byte
+    pub fn inverse(&self, source: &str) -> Edit {
+        let mut inverse = Edit::new();
+        // How much the splices before the current one moved it
+        let mut shift = 0isize;
+
+        for splice in &self.splices {
+            let start = splice.range.start.wrapping_add_signed(shift);
+            inverse.splices.push(Splice {
+                range: start..start + splice.text.len(),
+                text: source[splice.range.clone()].to_string(),
+            });
+            shift += splice.text.len() as isize - splice.range.len() as isize;
+        }
+
+        inverse
+    }
+}
+
+/// An edit operation on the board, before it is turned into an [`Edit`] on the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", rename_all = "snake_case"))]
+pub enum Operation {
+    SetChecked {
+        item: ItemPath,
+        checked: bool,
+    },
+    AddCheckbox {
+        item: ItemPath,
+        checked: bool,
+    },
+    Rename {
+        item: ItemPath,
+        text: String,
+    },
+    /// Changes the text of an item, rewriting only the part that differs from its current text,
+    /// so markup and line breaks elsewhere in the item are kept. `text` is in the form given by
+    /// [`Span::<Item>::editable_text`].
+    ReplaceText {
+        item: ItemPath,
+        text: String,
+    },
+    /// Inserts a new item at `index` of `list`. The list is created if it's the nested list of an
+    /// item that doesn't have one yet, or the first list of a section that has none.
+    Insert {
+        list: ListPath,
+        index: usize,
+        text: String,
+        checkbox: Option<bool>,
+    },
+    Delete {
+        item: ItemPath,
+    },
+    /// Moves an item, along with its nested list, to `index` of `list`. The index is taken before
+    /// the item is removed from its current place.
+    Move {
+        item: ItemPath,
+        list: ListPath,
+        index: usize,
+    },
+    /// Sets, changes or removes the due date of an item
+    SetDue {
+        item: ItemPath,
+        due: Option<NaiveDate>,
+    },
+    SetPriority {
+        item: ItemPath,
+        priority: Option<Priority>,
+    },
+    AddTag {
+        item: ItemPath,
+        tag: String,
+    },
+    RemoveTag {
+        item: ItemPath,
+        tag: String,
+    },
+    AddAssignee {
+        item: ItemPath,
+        assignee: String,
+    },
+    RemoveAssignee {
+        item: ItemPath,
+        assignee: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "detail", rename_all = "snake_case")
+)]
+pub enum EditError {
+    NoSuchItem(ItemPath),
+    NoSuchList(ListPath),
+    IndexOutOfBounds {
+        list: ListPath,
+        index: usize,
+    },
+    NoCheckbox(ItemPath),
+    HasCheckbox(ItemPath),
+    MoveIntoItself(ItemPath),
+    /// None of the grammars in the parse options has a syntax for the field
+    NoGrammar(Field),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::NoSuchItem(path) => write!(f, "no item at {path}"),
+            EditError::NoSuchList(path) => write!(f, "no list at {path}"),
+            EditError::IndexOutOfBounds { list, index } => {
+                write!(f, "index {index} is out of bounds for list {list}")
+            }
+            EditError::NoCheckbox(path) => write!(f, "item {path} has no checkbox"),
+            EditError::HasCheckbox(path) => write!(f, "item {path} already has a checkbox"),
+            EditError::MoveIntoItself(path) => {
+                write!(f, "item {path} can't be moved into its own nested list")
+            }
+            EditError::NoGrammar(field) => write!(f, "no grammar can write {field:?}"),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Start of the line that contains `pos`
+fn line_start(source: &str, pos: usize) -> usize {
+    source[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Whether `source` uses CRLF line endings, going by its first line
+fn uses_crlf(source: &str) -> bool {
+    source
+        .find('\n')
+        .is_some_and(|i| source[..i].ends_with('\r'))
+}
+
+/// The edit, with the newlines it adds written as CRLF if that's what `source` uses. Edits are
+/// made with plain newlines, and the ones copied from the source already have their CR.
+fn with_line_endings(source: &str, mut edit: Edit) -> Edit {
+    if !uses_crlf(source) {
+        return edit;
+    }
+
+    for splice in &mut edit.splices {
+        let mut text = String::with_capacity(splice.text.len());
+        let mut after_cr = source[..splice.range.start].ends_with('\r');
+        for c in splice.text.chars() {
+            if c == '\n' && !after_cr {
+                text.push('\r');
+            }
+            text.push(c);
+            after_cr = c == '\r';
+        }
+        splice.text = text;
+    }
+
+    edit
+}
+
+/// End of the line that contains `pos`, after its newline
+fn line_end(source: &str, pos: usize) -> usize {
+    source[pos..]
+        .find('\n')
+        .map_or(source.len(), |i| pos + i + 1)
+}
+
+/// `range`, without its trailing blank lines
+fn trim_blank_lines(source: &str, range: Range) -> Range {
+    let trimmed = source[range.clone()].trim_end();
+
+    if trimmed.is_empty() {
+        return range.start..range.start;
+    }
+
+    let end = range.start + trimmed.len();
+    range.start..line_end(source, end).min(range.end)
+}
+
+/// Where the item marker starts. Item ranges may start with part of the item indentation.
+fn marker_start(source: &str, item: &Span<Item>) -> usize {
+    let text = &source[item.range.clone()];
+    item.range.start + text.len() - text.trim_start_matches([' ', '\t']).len()
+}
+
+/// What comes before the item marker on its line: indentation, and maybe `>` of block quotes
+fn indentation<'a>(source: &'a str, item: &Span<Item>) -> &'a str {
+    let marker_start = marker_start(source, item);
+    let prefix = &source[line_start(source, marker_start)..marker_start];
+
+    if prefix.chars().all(|c| matches!(c, ' ' | '\t' | '>')) {
+        prefix
+    } else {
+        // The item starts in the same line as its parent, like `- - item`
+        ""
+    }
+}
+
+/// The list marker of an item and the whitespace after it, like `- ` or `10. `
+fn marker<'a>(source: &'a str, item: &Span<Item>) -> &'a str {
+    let rest = &source[marker_start(source, item)..item.range.end];
+    let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let spaces = rest[len..].len() - rest[len..].trim_start_matches([' ', '\t']).len();
+
+    &rest[..len + spaces]
+}
+
+/// Start of the item's first line, or the item start if it shares its line with something else
+fn item_line_start(source: &str, item: &Span<Item>) -> usize {
+    marker_start(source, item) - indentation(source, item).len()
+}
+
+/// Formats a new item. Lines after the first are aligned with the text of the first line.
+fn format_item(indent: &str, marker: &str, checkbox: Option<bool>, text: &str) -> String {
+    let mut marker = marker.to_string();
+    if !marker.ends_with([' ', '\t']) {
+        marker.push(' ');
+    }
+
+    let checkbox = match checkbox {
+        Some(true) => "[x] ",
+        Some(false) => "[ ] ",
+        None => "",
+    };
+
+    let continuation = format!("{indent}{}", " ".repeat(marker.len() + checkbox.len()));
+
+    let mut out = String::new();
+
+    for (i, line) in text.split('\n').enumerate() {
+        if i == 0 {
+            out.push_str(indent);
+            out.push_str(&marker);
+            out.push_str(checkbox);
+        } else if !line.is_empty() {
+            out.push_str(&continuation);
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Whether list items are separated by blank lines
+fn is_loose(source: &str, list: &Span<List>) -> bool {
+    let items = &list.element.items;
+
+    items
+        .iter()
+        .take(items.len().saturating_sub(1))
+        .any(|item| trim_blank_lines(source, item.range.clone()).end < item.range.end)
+}
+
+/// The item text, or an empty range after its checkbox or marker if it has no text
+fn text_or_end(source: &str, item: &Span<Item>) -> Range {
+    item.text_range(source).unwrap_or_else(|| {
+        let pos = match &item.element.checkbox {
+            Some(checkbox) => checkbox.range.end,
+            None => marker_start(source, item) + marker(source, item).len(),
+        };
+        pos..pos
+    })
+}
+
+/// The indentation of the continuation lines of an item whose text starts at `text_start`
+fn continuation(source: &str, item: &Span<Item>, text_start: usize) -> String {
+    format!(
+        "{}{}",
+        indentation(source, item),
+        " ".repeat(text_start - marker_start(source, item))
+    )
+}
+
+/// Indents the lines of `text` after the first, leaving empty lines empty
+fn indent_lines(text: &str, continuation: &str) -> String {
+    let mut out = String::new();
+
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+            if !line.is_empty() {
+                out.push_str(continuation);
+            }
+        }
+        out.push_str(line);
+    }
+
+    out
+}
+
+/// The lines of the item text in `range`, each without the indentation it has as a continuation
+/// line
+fn text_pieces(source: &str, item: &Span<Item>, range: Range) -> Vec<Range> {
+    let indent = indentation(source, item);
+    let mut pieces = vec![];
+    let mut start = range.start;
+
+    for (i, line) in source[range.clone()].split_inclusive('\n').enumerate() {
+        let end = start + line.len();
+        let text = if i == 0 {
+            line
+        } else {
+            line.strip_prefix(indent)
+                .unwrap_or(line)
+                .trim_start_matches([' ', '\t'])
+        };
+        // CRLF line endings are edited as plain newlines
+        if text.ends_with("\r\n") {
+            pieces.push(end - text.len()..end - 2);
+            pieces.push(end - 1..end);
+        } else {
+            pieces.push(end - text.len()..end);
+        }
+        start = end;
+    }
+
+    pieces
+}
+
+/// Splits text into words, runs of spaces, newlines and single punctuation characters
+fn tokens(text: &str) -> Vec<Range> {
+    let class = |c: char| match c {
+        '\n' => 0,
+        c if c.is_whitespace() => 1,
+        c if c.is_alphanumeric() || c == '_' => 2,
+        _ => 3,
+    };
+
+    let mut tokens: Vec<Range> = vec![];
+    let mut last = None;
+
+    for (i, c) in text.char_indices() {
+        let current = class(c);
+        match tokens.last_mut() {
+            Some(token) if last == Some(current) && matches!(current, 1 | 2) => {
+                token.end = i + c.len_utf8()
+            }
+            _ => tokens.push(i..i + c.len_utf8()),
+        }
+        last = Some(current);
+    }
+
+    tokens
+}
+
+/// The parts of `old` that differ from `new`, and what they become, as pairs of byte ranges.
+/// Compares words, so that a change inside a line doesn't rewrite the markup around it.
+fn word_diff(old: &str, new: &str) -> Vec<(Range, Range)> {
+    let a = tokens(old);
+    let b = tokens(new);
+    let same = |i: usize, j: usize| old[a[i].clone()] == new[b[j].clone()];
+
+    let prefix = (0..a.len().min(b.len()))
+        .take_while(|&i| same(i, i))
+        .count();
+    let suffix = (0..a.len().min(b.len()) - prefix)
+        .take_while(|&i| same(a.len() - 1 - i, b.len() - 1 - i))
+        .count();
+    let (n, m) = (a.len() - prefix - suffix, b.len() - prefix - suffix);
+
+    let start =
+        |tokens: &[Range], text: &str, i: usize| tokens.get(i).map_or(text.len(), |t| t.start);
+
+    // Longest common subsequence of the tokens between the common prefix and suffix. Very long
+    // texts are replaced in one piece instead.
+    let mut matches = vec![];
+    if n > 0 && m > 0 && n * m <= 1 << 20 {
+        let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i][j] = if same(prefix + i, prefix + j) {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if same(prefix + i, prefix + j) {
+                matches.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    matches.push((a.len() - suffix, b.len() - suffix));
+
+    let mut changes = vec![];
+    let (mut i, mut j) = (prefix, prefix);
+
+    for (next_i, next_j) in matches {
+        if next_i > i || next_j > j {
+            changes.push((
+                start(&a, old, i)..start(&a, old, next_i),
+                start(&b, new, j)..start(&b, new, next_j),
+            ));
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+
+    changes
+}
+
+impl Span<Item> {
+    /// The item text as it's edited in a text box: the raw markdown, with the indentation of
+    /// continuation lines taken out. See [`Operation::ReplaceText`].
+    pub fn editable_text(&self, source: &str) -> String {
+        self.text_range(source).map_or(String::new(), |range| {
+            text_pieces(source, self, range)
+                .into_iter()
+                .map(|piece| &source[piece])
+                .collect()
+        })
+    }
+}
+
+/// Where a new item goes, and how it should look
+struct Insertion {
+    pos: usize,
+    /// Text to put before the new item, like a newline when inserting at the end of a file that
+    /// doesn't end with one
+    before: String,
+    /// Text to put after the new item, like a blank line in loose lists
+    after: &'static str,
+    indent: String,
+    marker: String,
+}
+
+impl Board {
+    /// Turns an operation into an edit on `source`, which must be the text this board was parsed
+    /// from. New lines end like the ones of `source`, with CRLF or LF.
+    pub fn edit(&self, source: &str, operation: &Operation) -> Result<Edit, EditError> {
+        let edit = match operation {
+            Operation::SetChecked { item, checked } => self.set_checked(source, item, *checked),
+            Operation::AddCheckbox { item, checked } => self.add_checkbox(source, item, *checked),
+            Operation::Rename { item, text } => self.rename(source, item, text),
+            Operation::ReplaceText { item, text } => self.replace_text(source, item, text),
+            Operation::Insert {
+                list,
+                index,
+                text,
+                checkbox,
+            } => self.insert(source, list, *index, text, *checkbox),
+            Operation::Delete { item } => self.delete(source, item),
+            Operation::Move { item, list, index } => self.move_item(source, item, list, *index),
+            Operation::SetDue { item, due } => self.set_due(source, item, *due),
+            Operation::SetPriority { item, priority } => self.set_priority(source, item, *priority),
+            Operation::AddTag { item, tag } => {
+                self.add_field(source, item, Field::Tag(tag.trim_start_matches('#').into()))
+            }
+            Operation::RemoveTag { item, tag } => self.remove_field(
+                source,
+                item,
+                &Field::Tag(tag.trim_start_matches('#').into()),
+            ),
+            Operation::AddAssignee { item, assignee } => self.add_field(
+                source,
+                item,
+                Field::Assignee(assignee.trim_start_matches('@').into()),
+            ),
+            Operation::RemoveAssignee { item, assignee } => self.remove_field(
+                source,
+                item,
+                &Field::Assignee(assignee.trim_start_matches('@').into()),
+            ),
+        }?;
+
+        Ok(with_line_endings(source, edit))
+    }
+
+    /// Applies an operation, returning the new source and the board parsed from it
+    pub fn apply(&self, source: &str, operation: &Operation) -> Result<(String, Board), EditError> {
+        let new_source = self.edit(source, operation)?.apply(source);
+        let (board, _) = Board::parse_with(&new_source, &self.options);
+
+        Ok((new_source, board))
+    }
+
+    fn get_item(&self, path: &ItemPath) -> Result<&Span<Item>, EditError> {
+        self.item(path)
+            .ok_or_else(|| EditError::NoSuchItem(path.clone()))
+    }
+
+    /// Checks or unchecks an item by rewriting the single mark character of its checkbox.
+    ///
+    /// Checking uses the mark the document already uses for checked items (`x` or `X`), and an
+    /// item that is already in the requested state is left alone.
+    pub fn set_checked(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        checked: bool,
+    ) -> Result<Edit, EditError> {
+        let item = self.get_item(path)?;
+        let checkbox = item
+            .element
+            .checkbox
+            .as_ref()
+            .ok_or_else(|| EditError::NoCheckbox(path.clone()))?;
+
+        let mut edit = Edit::new();
+        if checkbox.element != checked {
+            let mark = checkbox.range.start + 1;
+            let new_mark = if checked {
+                self.checked_mark(source)
+            } else {
+                ' '
+            };
+            edit.splice(mark..mark + 1, new_mark);
+        }
+
+        Ok(edit)
+    }
+
+    /// Adds a checkbox to an item that is a plain bullet
+    pub fn add_checkbox(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        checked: bool,
+    ) -> Result<Edit, EditError> {
+        let item = self.get_item(path)?;
+        if item.element.checkbox.is_some() {
+            return Err(EditError::HasCheckbox(path.clone()));
+        }
+
+        let marker = marker(source, item);
+        let pos = marker_start(source, item) + marker.len();
+        let mark = if checked {
+            self.checked_mark(source)
+        } else {
+            ' '
+        };
+
+        let mut edit = Edit::new();
+        if marker.ends_with([' ', '\t']) {
+            edit.splice(pos..pos, format!("[{mark}] "));
+        } else {
+            edit.splice(pos..pos, format!(" [{mark}]"));
+        }
+
+        Ok(edit)
+    }
+
+    /// The mark of the first checked checkbox, or `x` if there is none
+    fn checked_mark(&self, source: &str) -> char {
+        self.items()
+            .into_iter()
+            .filter_map(|(_, item)| item.element.checkbox.as_ref())
+            .find(|checkbox| checkbox.element)
+            .and_then(|checkbox| source[checkbox.range.start + 1..].chars().next())
+            .unwrap_or('x')
+    }
+
+    /// Replaces the whole text of an item, keeping its marker, checkbox and nested list
+    pub fn rename(&self, source: &str, path: &ItemPath, text: &str) -> Result<Edit, EditError> {
+        let item = self.get_item(path)?;
+
+        let range = text_or_end(source, item);
+
+        let mut new_text = String::new();
+        if range.is_empty() && !source[..range.start].ends_with([' ', '\t']) {
+            new_text.push(' ');
+        }
+
+        new_text.push_str(&indent_lines(
+            text,
+            &continuation(source, item, range.start),
+        ));
+
+        let mut edit = Edit::new();
+        edit.splice(range, new_text);
+
+        Ok(edit)
+    }
+
+    /// Changes the text of an item to `text`, which is compared with the
+    /// [editable text](Span::<Item>::editable_text) of the item. Only the part between their
+    /// common prefix and suffix is spliced, so untouched emphasis, links and code spans stay as
+    /// they are, and so does the indentation of untouched continuation lines.
+    pub fn replace_text(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        text: &str,
+    ) -> Result<Edit, EditError> {
+        let item = self.get_item(path)?;
+
+        let Some(range) = item.text_range(source) else {
+            return self.rename(source, path, text);
+        };
+
+        let pieces = text_pieces(source, item, range.clone());
+        let old: String = pieces.iter().map(|p| &source[p.clone()]).collect();
+
+        // Where a position in the editable text is in the source. A position at the start of a
+        // continuation line is after its indentation.
+        let to_source = |pos: usize| {
+            let mut view_start = 0;
+            let mut found = range.start;
+            for piece in &pieces {
+                if view_start > pos {
+                    break;
+                }
+                found = piece.start + (pos - view_start);
+                view_start += piece.len();
+            }
+            // Not between the CR and LF of a line ending
+            if source[..found].ends_with('\r') && source[found..].starts_with('\n') {
+                found -= 1;
+            }
+            found
+        };
+
+        let continuation = continuation(source, item, range.start);
+        let mut edit = Edit::new();
+
+        for (old_range, new_range) in word_diff(&old, text) {
+            let new_text = &text[new_range];
+            let mut replacement = indent_lines(new_text, &continuation);
+            if new_text.ends_with('\n') && old_range.end < old.len() {
+                // The kept text after the splice starts a new line
+                replacement.push_str(&continuation);
+            }
+            edit.splice(
+                to_source(old_range.start)..to_source(old_range.end),
+                replacement,
+            );
+        }
+
+        Ok(edit)
+    }
+
+    /// Sets, changes or removes the due date of an item. An existing date is rewritten in place,
+    /// in the grammar it was written in; a new one is added at the end of the item text.
+    pub fn set_due(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        due: Option<NaiveDate>,
+    ) -> Result<Edit, EditError> {
+        match due {
+            Some(date) => self.set_field(source, path, Field::Due(date)),
+            None => self.remove_fields(source, path, |f| matches!(f, Field::Due(_))),
+        }
+    }
+
+    /// Sets, changes or removes the priority of an item, like [`Board::set_due`]
+    pub fn set_priority(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        priority: Option<Priority>,
+    ) -> Result<Edit, EditError> {
+        match priority {
+            Some(priority) => self.set_field(source, path, Field::Priority(priority)),
+            None => self.remove_fields(source, path, |f| matches!(f, Field::Priority(_))),
+        }
+    }
+
+    /// Writes a field with a single value per item, like a due date, replacing the token of the
+    /// current value if there is one
+    pub fn set_field(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        field: Field,
+    ) -> Result<Edit, EditError> {
+        let item = self.get_item(path)?;
+        let grammars = &self.options.grammars;
+
+        let Some(current) = item
+            .element
+            .metadata
+            .fields()
+            .into_iter()
+            .find(|f| f.element.same_kind(&field))
+        else {
+            return self.add_field(source, path, field);
+        };
+
+        let mut edit = Edit::new();
+        if current.element != field {
+            let text = metadata::grammar_of(source, current.range.clone(), &field, grammars)
+                .and_then(|g| g.write(&field))
+                .or_else(|| grammars.iter().find_map(|g| g.write(&field)))
+                .ok_or_else(|| EditError::NoGrammar(field.clone()))?;
+            edit.splice(current.range, text);
+        }
+
+        Ok(edit)
+    }
+
+    /// Adds a field to the item text, written with the first grammar that has a syntax for it.
+    /// Fields that are already there are left alone.
+    pub fn add_field(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        field: Field,
+    ) -> Result<Edit, EditError> {
+        let item = self.get_item(path)?;
+
+        let mut edit = Edit::new();
+        if item
+            .element
+            .metadata
+            .fields()
+            .iter()
+            .any(|f| f.element == field)
+        {
+            return Ok(edit);
+        }
+
+        let (grammar, text) = self
+            .options
+            .grammars
+            .iter()
+            .find_map(|g| Some((g, g.write(&field)?)))
+            .ok_or_else(|| EditError::NoGrammar(field.clone()))?;
+
+        let range = text_or_end(source, item);
+        if range.is_empty() {
+            let space = if source[..range.start].ends_with([' ', '\t']) {
+                ""
+            } else {
+                " "
+            };
+            edit.splice(range.clone(), format!("{space}{text}"));
+        } else if grammar.leading(&field) {
+            edit.splice(range.start..range.start, format!("{text} "));
+        } else {
+            edit.splice(range.end..range.end, format!(" {text}"));
+        }
+
+        Ok(edit)
+    }
+
+    /// Removes every occurrence of a field from the item text
+    pub fn remove_field(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        field: &Field,
+    ) -> Result<Edit, EditError> {
+        self.remove_fields(source, path, |f| f == field)
+    }
+
+    fn remove_fields(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        matches: impl Fn(&Field) -> bool,
+    ) -> Result<Edit, EditError> {
+        let item = self.get_item(path)?;
+        let text = text_or_end(source, item);
+        // Where the remaining text starts, after the fields removed from its start
+        let mut text_start = text.start;
+
+        let mut edit = Edit::new();
+        for field in item.element.metadata.fields() {
+            if !matches(&field.element) {
+                continue;
+            }
+
+            // Take the space before the token along, or the one after it when the token starts the
+            // item text
+            let range = field.range;
+            let before = source[..range.start].trim_end_matches([' ', '\t']).len();
+            let after = source.len() - source[range.end..].trim_start_matches([' ', '\t']).len();
+            if range.start > text_start && before >= text_start {
+                edit.splice(before..range.end, "");
+            } else if after < text.end {
+                edit.splice(range.start..after, "");
+                text_start = after;
+            } else {
+                edit.splice(range, "");
+            }
+        }
+
+        Ok(edit)
+    }
+
+    pub fn insert(
+        &self,
+        source: &str,
+        list: &ListPath,
+        index: usize,
+        text: &str,
+        checkbox: Option<bool>,
+    ) -> Result<Edit, EditError> {
+        let mut insertion = self.insertion(source, list, index)?;
+
+        let mut edit = Edit::new();
+
+        if let Some(target) = self.list(list) {
+            let mut order: Vec<_> = (0..target.element.items.len()).map(Some).collect();
+            order.insert(index, None);
+            if let Some(number) = renumber(source, target, &order, &mut edit) {
+                insertion.marker = with_number(&insertion.marker, number);
+            }
+        }
+
+        edit.splice(
+            insertion.pos..insertion.pos,
+            format!(
+                "{}{}{}",
+                insertion.before,
+                format_item(&insertion.indent, &insertion.marker, checkbox, text),
+                insertion.after
+            ),
+        );
+
+        Ok(edit)
+    }
+
+    pub fn delete(&self, source: &str, path: &ItemPath) -> Result<Edit, EditError> {
+        let range = self.removal_range(source, path)?;
+
+        let mut edit = Edit::new();
+        edit.splice(range, "");
+
+        let list = self.list(&path.list).unwrap();
+        let order: Vec<_> = (0..list.element.items.len())
+            .filter(|&i| i != path.index)
+            .map(Some)
+            .collect();
+        renumber(source, list, &order, &mut edit);
+
+        Ok(edit)
+    }
+
+    pub fn move_item(
+        &self,
+        source: &str,
+        path: &ItemPath,
+        list: &ListPath,
+        index: usize,
+    ) -> Result<Edit, EditError> {
+        if list.is_inside(path) {
+            return Err(EditError::MoveIntoItself(path.clone()));
+        }
+
+        let item = self.get_item(path)?;
+
+        // Moving an item right before or after itself
+        if *list == path.list && (index == path.index || index == path.index + 1) {
+            return Ok(Edit::new());
+        }
+
+        let removal = self.removal_range(source, path)?;
+        let mut insertion = self.insertion(source, list, index)?;
+
+        let mut edit = Edit::new();
+        edit.splice(removal, "");
+
+        let from_list = self.list(&path.list).unwrap();
+        let mut from_order: Vec<_> = (0..from_list.element.items.len())
+            .filter(|&i| i != path.index)
+            .map(Some)
+            .collect();
+
+        if *list == path.list {
+            let position = if index > path.index { index - 1 } else { index };
+            from_order.insert(position, None);
+        } else if let Some(target) = self.list(list) {
+            let mut order: Vec<_> = (0..target.element.items.len()).map(Some).collect();
+            order.insert(index, None);
+            if let Some(number) = renumber(source, target, &order, &mut edit) {
+                insertion.marker = with_number(&insertion.marker, number);
+            }
+        }
+
+        if let Some(number) = renumber(source, from_list, &from_order, &mut edit) {
+            insertion.marker = with_number(&insertion.marker, number);
+        }
+
+        let from_indent = indentation(source, item);
+        let from_marker = marker(source, item);
+        let to_marker = retarget_marker(from_marker, &insertion.marker);
+        let start = item_line_start(source, item);
+        let block = &source[start..trim_blank_lines(source, item.range.clone()).end];
+
+        let mut moved = insertion.before.clone();
+
+        for (i, line) in block.split_inclusive('\n').enumerate() {
+            if i == 0 {
+                moved.push_str(&insertion.indent);
+                moved.push_str(&to_marker);
+                moved.push_str(&line[from_indent.len() + from_marker.len()..]);
+            } else if line.trim().is_empty() {
+                moved.push_str(line.trim_start_matches([' ', '\t', '>']));
+            } else {
+                let rest = line.strip_prefix(from_indent).unwrap_or_else(|| {
+                    let whitespace = line.len() - line.trim_start().len();
+                    &line[whitespace.min(from_indent.len())..]
+                });
+                moved.push_str(&insertion.indent);
+                moved.push_str(&shift_indentation(rest, from_marker.len(), to_marker.len()));
+            }
+        }
+        if !moved.ends_with('\n') {
+            moved.push('\n');
+        }
+        moved.push_str(insertion.after);
+
+        edit.splice(insertion.pos..insertion.pos, moved);
+
+        Ok(edit)
+    }
+
+    /// The source range to remove when an item is deleted: its lines, and the blank lines that
+    /// separate it from its siblings
+    fn removal_range(&self, source: &str, path: &ItemPath) -> Result<Range, EditError> {
+        let item = self.get_item(path)?;
+        let items = &self.list(&path.list).unwrap().element.items;
+
+        let range = if let Some(next) = items.get(path.index + 1) {
+            item_line_start(source, item)..item_line_start(source, next)
+        } else if path.index > 0 {
+            let previous = &items[path.index - 1];
+            let end = trim_blank_lines(source, item.range.clone()).end;
+            trim_blank_lines(source, previous.range.clone()).end..end
+        } else if path.list.parents.is_empty() {
+            // The list goes away, along with the blank lines that separated it from what follows
+            item_line_start(source, item)..item.range.end
+        } else {
+            let end = trim_blank_lines(source, item.range.clone()).end;
+            item_line_start(source, item)..end
+        };
+
+        Ok(range)
+    }
+
+    fn insertion(
+        &self,
+        source: &str,
+        path: &ListPath,
+        index: usize,
+    ) -> Result<Insertion, EditError> {
+        let out_of_bounds = || EditError::IndexOutOfBounds {
+            list: path.clone(),
+            index,
+        };
+
+        let at_end = |pos: usize| {
+            if pos == source.len() && !source.is_empty() && !source.ends_with('\n') {
+                String::from("\n")
+            } else {
+                String::new()
+            }
+        };
+
+        if let Some(list) = self.list(path) {
+            let items = &list.element.items;
+            let loose = is_loose(source, list);
+
+            if let Some(item) = items.get(index) {
+                return Ok(Insertion {
+                    pos: item_line_start(source, item),
+                    before: String::new(),
+                    after: if loose { "\n" } else { "" },
+                    indent: indentation(source, item).to_string(),
+                    marker: marker(source, item).to_string(),
+                });
+            }
+
+            if index != items.len() {
+                return Err(out_of_bounds());
+            }
+
+            let last = items.last().ok_or_else(out_of_bounds)?;
+            let pos = trim_blank_lines(source, last.range.clone()).end;
+            let mut before = at_end(pos);
+            if loose {
+                before.push('\n');
+            }
+
+            return Ok(Insertion {
+                pos,
+                before,
+                after: "",
+                indent: indentation(source, last).to_string(),
+                marker: marker(source, last).to_string(),
+            });
+        }
+
+        if index != 0 {
+            return Err(out_of_bounds());
+        }
+
+        let no_such_list = || EditError::NoSuchList(path.clone());
+
+        match path.parent() {
+            // A new nested list
+            Some(parent_path) => {
+                let parent = self.item(&parent_path).ok_or_else(no_such_list)?;
+                if parent.element.nested_list.is_some() {
+                    return Err(no_such_list());
+                }
+
+                let parent_marker = marker(source, parent);
+                let pos = trim_blank_lines(source, parent.range.clone()).end;
+
+                Ok(Insertion {
+                    pos,
+                    before: at_end(pos),
+                    after: "",
+                    indent: format!(
+                        "{}{}",
+                        indentation(source, parent),
+                        " ".repeat(parent_marker.len())
+                    ),
+                    marker: bullet_marker(parent_marker).to_string(),
+                })
+            }
+            // The first list of an empty document
+            None if self.sections.is_empty() && path.section == 0 && path.list == 0 => {
+                let pos = source.len();
+                let before = match source {
+                    crate::BOM => String::new(),
+                    _ => at_end(pos),
+                };
+
+                Ok(Insertion {
+                    pos,
+                    before,
+                    after: "",
+                    indent: String::new(),
+                    marker: String::from("- "),
+                })
+            }
+            // The first list of a section
+            None => {
+                let section = self.sections.get(path.section).ok_or_else(no_such_list)?;
+                if path.list != 0 || !section.element.lists.is_empty() {
+                    return Err(no_such_list());
+                }
+
+                let pos = trim_blank_lines(source, section.range.clone()).end;
+                let mut before = at_end(pos);
+                if pos > 0 {
+                    before.push('\n');
+                }
+
+                Ok(Insertion {
+                    pos,
+                    before,
+                    after: "",
+                    indent: String::new(),
+                    marker: String::from("- "),
+                })
+            }
+        }
+    }
+}
+
+/// Range of the number in the marker of an ordered list item
+fn number_range(source: &str, item: &Span<Item>) -> Range {
+    let start = marker_start(source, item);
+    let digits = source[start..item.range.end]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(0);
+
+    start..start + digits
+}
+
+/// Replaces the number of an ordered list marker
+fn with_number(marker: &str, number: u64) -> String {
+    let digits = marker
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(marker.len());
+
+    format!("{number}{}", &marker[digits..])
+}
+
+/// Renumbers an ordered list after items are added to it or removed from it. `order` has the
+/// current index of each item in their new order, or `None` for an item that is new to the list,
+/// whose number is returned.
+///
+/// Only numbers that change are rewritten. Lists where every item has the same number, like a
+/// list of `1.`, are left alone and new items get that number too.
+fn renumber(
+    source: &str,
+    list: &Span<List>,
+    order: &[Option<usize>],
+    edit: &mut Edit,
+) -> Option<u64> {
+    let ListKind::Ordered { start, .. } = list.element.kind else {
+        return None;
+    };
+
+    let items = &list.element.items;
+    let numbers: Vec<_> = items
+        .iter()
+        .map(|item| &source[number_range(source, item)])
+        .collect();
+    let lazy = numbers.len() > 1 && numbers.iter().all(|n| *n == numbers[0]);
+
+    let mut new_number = None;
+
+    for (position, index) in order.iter().enumerate() {
+        let number = if lazy { start } else { start + position as u64 };
+
+        match index {
+            Some(index) => {
+                if numbers[*index] != number.to_string() {
+                    edit.splice(number_range(source, &items[*index]), number.to_string());
+                }
+            }
+            None => new_number = Some(number),
+        }
+    }
+
+    new_number
+}
+
+/// Re-indents a line after the first of a moved item, so that it keeps its place relative to the
+/// item text when the marker width changes, like from `- ` to `10. `. Lines that are indented
+/// less than the item text are lazy continuations and are kept as they are.
+fn shift_indentation(line: &str, from_width: usize, to_width: usize) -> String {
+    let rest = line.trim_start_matches(' ');
+    let spaces = line.len() - rest.len();
+
+    if spaces < from_width || rest.starts_with('\t') {
+        line.to_string()
+    } else {
+        format!("{}{rest}", " ".repeat(spaces - from_width + to_width))
+    }
+}
+
+/// The marker for a new nested list: the parent's bullet if it has one, `-` otherwise
+fn bullet_marker(parent_marker: &str) -> &'static str {
+    match parent_marker.trim_end() {
+        "*" => "* ",
+        "+" => "+ ",
+        _ => "- ",
+    }
+}
+
+/// The marker of a moved item: the target list's marker, keeping the spacing the item had
+fn retarget_marker(from: &str, to: &str) -> String {
+    let from_symbol = from.trim_end();
+    let to_symbol = to.trim_end();
+
+    if from_symbol == to_symbol {
+        from.to_string()
+    } else {
+        format!("{to_symbol}{}", &from[from_symbol.len()..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Inline, ObsidianTasks, ParseOptions, TodoTxt};
+    use rand::prelude::*;
+    use std::sync::Arc;
+
+    const SOURCE: &str = "\
+## Todo
+
+- [ ] first
+- [x] second
+  - [ ] nested
+* [ ] other list
+
+## Done
+
+- [x] third
+";
+
+    fn apply(operation: Operation) -> String {
+        let (board, _) = Board::parse(SOURCE);
+        board.apply(SOURCE, &operation).unwrap().0
+    }
+
+    #[test]
+    fn insert_into_empty_document() {
+        for (source, expected) in [
+            ("", "- [ ] x\n"),
+            ("\n", "\n- [ ] x\n"),
+            ("\u{feff}", "\u{feff}- [ ] x\n"),
+        ] {
+            let (board, _) = Board::parse(source);
+            let operation = Operation::Insert {
+                list: ListPath::new(0, 0),
+                index: 0,
+                text: "x".into(),
+                checkbox: Some(false),
+            };
+            assert_eq!(board.apply(source, &operation).unwrap().0, expected);
+        }
+    }
+
+    #[test]
+    fn error_messages() {
+        let (board, _) = Board::parse(SOURCE);
+        let error = |operation| board.edit(SOURCE, &operation).unwrap_err().to_string();
+
+        assert_eq!(
+            error(Operation::SetChecked {
+                item: ItemPath::new(0, 0, 5),
+                checked: true,
+            }),
+            "no item at 0.0.5"
+        );
+        assert_eq!(
+            error(Operation::Move {
+                item: ItemPath::new(0, 0, 0),
+                list: ListPath::new(0, 0).item(1).nested(),
+                index: 3,
+            }),
+            "index 3 is out of bounds for list 0.0.1"
+        );
+    }
+
+    #[test]
+    fn set_checked() {
+        let new = apply(Operation::SetChecked {
+            item: ItemPath::new(0, 0, 0),
+            checked: true,
+        });
+        assert_eq!(new, SOURCE.replacen("- [ ] first", "- [x] first", 1));
+    }
+
+    #[test]
+    fn set_due_rewrites_only_the_date_token() {
+        let source = "- [ ] ship @alice due:2026-11-01 !high\n- [ ] other 📅 2026-01-01\n";
+        let options = ParseOptions {
+            grammars: vec![Arc::new(Inline), Arc::new(ObsidianTasks)],
+            ..ParseOptions::default()
+        };
+        let (board, _) = Board::parse_with(source, &options);
+        let date = "2027-02-03".parse().unwrap();
+
+        let edit = board
+            .set_due(source, &ItemPath::new(0, 0, 0), Some(date))
+            .unwrap();
+        assert_eq!(edit.splices().len(), 1);
+        assert_eq!(
+            edit.apply(source),
+            source.replacen("due:2026-11-01", "due:2027-02-03", 1)
+        );
+
+        // Dates are rewritten in the grammar they were written in
+        let edit = board
+            .set_due(source, &ItemPath::new(0, 0, 1), Some(date))
+            .unwrap();
+        assert_eq!(
+            edit.apply(source),
+            source.replacen("📅 2026-01-01", "📅 2027-02-03", 1)
+        );
+    }
+
+    #[test]
+    fn add_and_remove_fields() {
+        let source = "- [ ] #old first #old\n- [ ] second\n";
+        let (board, _) = Board::parse(source);
+
+        let new = board
+            .apply(
+                source,
+                &Operation::RemoveTag {
+                    item: ItemPath::new(0, 0, 0),
+                    tag: String::from("old"),
+                },
+            )
+            .unwrap()
+            .0;
+        assert_eq!(new, "- [ ] first\n- [ ] second\n");
+
+        let (new, new_board) = board
+            .apply(
+                source,
+                &Operation::SetPriority {
+                    item: ItemPath::new(0, 0, 1),
+                    priority: Some(Priority::High),
+                },
+            )
+            .unwrap();
+        assert_eq!(new, "- [ ] #old first #old\n- [ ] second !high\n");
+        let priority = &new_board.item(&ItemPath::new(0, 0, 1)).unwrap().element;
+        assert_eq!(
+            priority.metadata.priority.as_ref().unwrap().element,
+            Priority::High
+        );
+    }
+
+    #[test]
+    fn todo_txt_priorities_lead_the_text() {
+        let source = "- call mom +family\n";
+        let options = ParseOptions {
+            grammars: vec![Arc::new(TodoTxt)],
+            ..ParseOptions::default()
+        };
+        let (board, _) = Board::parse_with(source, &options);
+        let path = ItemPath::new(0, 0, 0);
+
+        let edit = board
+            .set_priority(source, &path, Some(Priority::Highest))
+            .unwrap();
+        assert_eq!(edit.apply(source), "- (A) call mom +family\n");
+
+        assert_eq!(
+            board.add_field(source, &path, Field::Assignee("bob".into())),
+            Err(EditError::NoGrammar(Field::Assignee("bob".into())))
+        );
+    }
+
+    #[test]
+    fn set_checked_rewrites_one_byte() {
+        let (board, _) = Board::parse(SOURCE);
+        let edit = board
+            .set_checked(SOURCE, &ItemPath::new(0, 0, 1), false)
+            .unwrap();
+
+        assert_eq!(edit.splices().len(), 1);
+        assert_eq!(edit.splices()[0].range.len(), 1);
+        assert_eq!(edit.splices()[0].text, " ");
+    }
+
+    #[test]
+    fn set_checked_keeps_the_mark_style() {
+        let source = "- [X] done\n- [ ] todo\n";
+        let (board, _) = Board::parse(source);
+
+        let edit = board
+            .set_checked(source, &ItemPath::new(0, 0, 1), true)
+            .unwrap();
+        assert_eq!(edit.apply(source), "- [X] done\n- [X] todo\n");
+
+        let edit = board
+            .set_checked(source, &ItemPath::new(0, 0, 0), true)
+            .unwrap();
+        assert!(edit.is_empty());
+    }
+
+    #[test]
+    fn set_checked_without_checkbox() {
+        let source = "- plain\n";
+        let (board, _) = Board::parse(source);
+        let item = ItemPath::new(0, 0, 0);
+
+        assert_eq!(
+            board.set_checked(source, &item, true),
+            Err(EditError::NoCheckbox(item.clone()))
+        );
+
+        let edit = board.add_checkbox(source, &item, false).unwrap();
+        assert_eq!(edit.apply(source), "- [ ] plain\n");
+
+        let source = "* [ ] task\n  + plain\n";
+        let (board, _) = Board::parse(source);
+        let item = ItemPath::new(0, 0, 0).child(0);
+        let edit = board.add_checkbox(source, &item, true).unwrap();
+        assert_eq!(edit.apply(source), "* [ ] task\n  + [x] plain\n");
+        assert_eq!(
+            board.add_checkbox(source, &ItemPath::new(0, 0, 0), true),
+            Err(EditError::HasCheckbox(ItemPath::new(0, 0, 0)))
+        );
+    }
+
+    #[test]
+    fn edit_between() {
+        for (old, new) in [
+            ("abc", "abc"),
+            ("abc", "aXc"),
+            ("aaa", "aa"),
+            ("", "new"),
+            ("- [ ] é\n", "- [ ] è\n"),
+        ] {
+            let edit = Edit::between(old, new);
+            assert_eq!(edit.apply(old), new);
+            assert_eq!(edit.inverse(old).apply(new), old);
+            assert!(edit.splices().len() <= 1);
+        }
+    }
+
+    #[test]
+    fn rename() {
+        let new = apply(Operation::Rename {
+            item: ItemPath::new(0, 0, 1),
+            text: "renamed".into(),
+        });
+        assert_eq!(new, SOURCE.replacen("second", "renamed", 1));
+    }
+
+    #[test]
+    fn replace_text_keeps_untouched_markup() {
+        let source = "- [ ] see *this* and [the docs](http://a.b) `x`\n";
+        let (board, _) = Board::parse(source);
+        let item = board.item(&ItemPath::new(0, 0, 0)).unwrap();
+        assert_eq!(
+            item.editable_text(source),
+            "see *this* and [the docs](http://a.b) `x`"
+        );
+
+        let edit = board
+            .replace_text(
+                source,
+                &ItemPath::new(0, 0, 0),
+                "read *this* and [the new docs](http://a.b) `x`",
+            )
+            .unwrap();
+        assert_eq!(
+            edit.apply(source),
+            "- [ ] read *this* and [the new docs](http://a.b) `x`\n"
+        );
+        assert_eq!(edit.splices().len(), 2);
+        let edit = board
+            .replace_text(source, &ItemPath::new(0, 0, 0), &item.editable_text(source))
+            .unwrap();
+        assert!(edit.is_empty());
+    }
+
+    #[test]
+    fn replace_text_of_wrapped_items() {
+        // Like test.md
+        let source = "- [ ] a *c*\n      b\n  - nested\n";
+        let (board, _) = Board::parse(source);
+        let path = ItemPath::new(0, 0, 0);
+        assert_eq!(board.item(&path).unwrap().editable_text(source), "a *c*\nb");
+
+        let replace = |text: &str| {
+            board
+                .replace_text(source, &path, text)
+                .unwrap()
+                .apply(source)
+        };
+        assert_eq!(replace("a *d*\nb"), "- [ ] a *d*\n      b\n  - nested\n");
+        assert_eq!(replace("a *c*\nbe"), "- [ ] a *c*\n      be\n  - nested\n");
+        assert_eq!(
+            replace("a *c*\nb\nthird"),
+            "- [ ] a *c*\n      b\n      third\n  - nested\n"
+        );
+        assert_eq!(
+            replace("a *c*\nnew\nb"),
+            "- [ ] a *c*\n      new\n      b\n  - nested\n"
+        );
+        assert_eq!(replace("a *c* b"), "- [ ] a *c* b\n  - nested\n");
+    }
+
+    #[test]
+    fn crlf_files_stay_crlf() {
+        let source = "\u{feff}## Todo\r\n\r\n- [ ] first\r\n  continued\r\n- [x] second\r\n";
+        let (board, _) = Board::parse(source);
+        let first = board.item(&ItemPath::new(0, 0, 0)).unwrap();
+        assert_eq!(first.editable_text(source), "first\ncontinued");
+
+        for (operation, expected) in [
+            (
+                Operation::ReplaceText {
+                    item: ItemPath::new(0, 0, 0),
+                    text: "first\ncontinued, edited\nnew line".into(),
+                },
+                "\u{feff}## Todo\r\n\r\n- [ ] first\r\n  continued, edited\r\n      new line\r\n- [x] second\r\n",
+            ),
+            (
+                Operation::Insert {
+                    list: ListPath::new(0, 0),
+                    index: 2,
+                    text: "third".into(),
+                    checkbox: Some(false),
+                },
+                "\u{feff}## Todo\r\n\r\n- [ ] first\r\n  continued\r\n- [x] second\r\n- [ ] third\r\n",
+            ),
+            (
+                Operation::Move {
+                    item: ItemPath::new(0, 0, 1),
+                    list: ListPath::new(0, 0),
+                    index: 0,
+                },
+                "\u{feff}## Todo\r\n\r\n- [x] second\r\n- [ ] first\r\n  continued\r\n",
+            ),
+        ] {
+            let edit = board.edit(source, &operation).unwrap();
+            let new = edit.apply(source);
+            assert_eq!(new, expected);
+            assert_eq!(edit.inverse(source).apply(&new), source);
+        }
+    }
+
+    #[test]
+    fn insert() {
+        let new = apply(Operation::Insert {
+            list: ListPath::new(0, 0),
+            index: 1,
+            text: "inserted".into(),
+            checkbox: Some(false),
+        });
+        assert_eq!(
+            new,
+            SOURCE.replacen("- [x] second", "- [ ] inserted\n- [x] second", 1)
+        );
+
+        let new = apply(Operation::Insert {
+            list: ListPath::new(1, 0),
+            index: 1,
+            text: "appended".into(),
+            checkbox: Some(true),
+        });
+        assert_eq!(new, format!("{SOURCE}- [x] appended\n"));
+
+        let new = apply(Operation::Insert {
+            list: ItemPath::new(0, 0, 0).nested(),
+            index: 0,
+            text: "sub task".into(),
+            checkbox: None,
+        });
+        assert_eq!(
+            new,
+            SOURCE.replacen("- [ ] first\n", "- [ ] first\n  - sub task\n", 1)
+        );
+    }
+
+    #[test]
+    fn insert_into_empty_section() {
+        let source = "## Todo\n\n## Done\n";
+        let (board, _) = Board::parse(source);
+        let operation = Operation::Insert {
+            list: ListPath::new(0, 0),
+            index: 0,
+            text: "task".into(),
+            checkbox: Some(false),
+        };
+        let (new, board) = board.apply(source, &operation).unwrap();
+
+        assert_eq!(new, "## Todo\n\n- [ ] task\n\n## Done\n");
+        assert_eq!(board.sections[0].element.lists.len(), 1);
+    }
+
+    #[test]
+    fn delete() {
+        let new = apply(Operation::Delete {
+            item: ItemPath::new(0, 0, 1),
+        });
+        assert_eq!(
+            new,
+            SOURCE.replacen("- [x] second\n  - [ ] nested\n", "", 1)
+        );
+    }
+
+    #[test]
+    fn move_between_sections() {
+        let new = apply(Operation::Move {
+            item: ItemPath::new(0, 0, 1),
+            list: ListPath::new(1, 0),
+            index: 0,
+        });
+        assert_eq!(
+            new,
+            "\
+## Todo
+
+- [ ] first
+* [ ] other list
+
+## Done
+
+- [x] second
+  - [ ] nested
+- [x] third
+"
+        );
+    }
+
+    #[test]
+    fn move_changes_indentation_and_marker() {
+        let new = apply(Operation::Move {
+            item: ItemPath::new(0, 0, 1).child(0),
+            list: ListPath::new(0, 1),
+            index: 1,
+        });
+        assert_eq!(
+            new,
+            SOURCE.replacen(
+                "  - [ ] nested\n* [ ] other list\n",
+                "* [ ] other list\n* [ ] nested\n",
+                1
+            )
+        );
+    }
+
+    const ORDERED: &str = "\
+## Todo
+
+1. first
+2. second
+   - nested
+3. third
+";
+
+    #[test]
+    fn insert_renumbers_ordered_lists() {
+        let (board, _) = Board::parse(ORDERED);
+        let edit = board
+            .insert(ORDERED, &ListPath::new(0, 0), 1, "inserted", None)
+            .unwrap();
+
+        assert_eq!(
+            edit.apply(ORDERED),
+            "## Todo\n\n1. first\n2. inserted\n3. second\n   - nested\n4. third\n"
+        );
+        // Only the digits of the following items change
+        assert_eq!(edit.splices().len(), 3);
+        assert!(edit.splices()[1..].iter().all(|s| s.range.len() == 1));
+    }
+
+    #[test]
+    fn delete_renumbers_ordered_lists() {
+        let (board, _) = Board::parse(ORDERED);
+        let edit = board.delete(ORDERED, &ItemPath::new(0, 0, 0)).unwrap();
+
+        assert_eq!(
+            edit.apply(ORDERED),
+            "## Todo\n\n1. second\n   - nested\n2. third\n"
+        );
+    }
+
+    #[test]
+    fn lazy_numbering_is_kept() {
+        let source = "1) a\n1) b\n";
+        let (board, _) = Board::parse(source);
+        let edit = board
+            .insert(source, &ListPath::new(0, 0), 0, "new", None)
+            .unwrap();
+
+        assert_eq!(edit.apply(source), "1) new\n1) a\n1) b\n");
+    }
+
+    #[test]
+    fn move_within_ordered_list() {
+        let (board, _) = Board::parse(ORDERED);
+        let edit = board
+            .move_item(ORDERED, &ItemPath::new(0, 0, 0), &ListPath::new(0, 0), 3)
+            .unwrap();
+
+        assert_eq!(
+            edit.apply(ORDERED),
+            "## Todo\n\n1. second\n   - nested\n2. third\n3. first\n"
+        );
+    }
+
+    #[test]
+    fn move_between_list_kinds_keeps_nesting() {
+        let source = "- a\n  - nested\n\n9. b\n";
+        let (board, _) = Board::parse(source);
+        let edit = board
+            .move_item(source, &ItemPath::new(0, 0, 0), &ListPath::new(0, 1), 1)
+            .unwrap();
+        let new = edit.apply(source);
+
+        assert_eq!(new, "9. b\n10. a\n    - nested\n");
+        let (board, _) = Board::parse(&new);
+        let moved = board.item(&ItemPath::new(0, 0, 1)).unwrap();
+        assert!(moved.element.nested_list.is_some());
+    }
+
+    #[test]
+    fn reordering_cuts_and_pastes_whole_lines() {
+        let source = "- a\n- b\n  - nested\n- c\n";
+        let (board, _) = Board::parse(source);
+        let edit = board
+            .move_item(source, &ItemPath::new(0, 0, 1), &ListPath::new(0, 0), 3)
+            .unwrap();
+
+        assert_eq!(edit.apply(source), "- a\n- c\n- b\n  - nested\n");
+        let splices: Vec<_> = edit
+            .splices()
+            .iter()
+            .map(|s| (&source[s.range.clone()], s.text.as_str()))
+            .collect();
+        assert_eq!(
+            splices,
+            [("- b\n  - nested\n", ""), ("", "- b\n  - nested\n")]
+        );
+    }
+
+    #[test]
+    fn move_into_itself() {
+        let (board, _) = Board::parse(SOURCE);
+        let item = ItemPath::new(0, 0, 1);
+        let operation = Operation::Move {
+            list: item.nested(),
+            item: item.clone(),
+            index: 0,
+        };
+
+        assert_eq!(
+            board.edit(SOURCE, &operation),
+            Err(EditError::MoveIntoItself(item))
+        );
+    }
+
+    #[test]
+    fn missing_item() {
+        let (board, _) = Board::parse(SOURCE);
+        let item = ItemPath::new(0, 0, 0).child(0);
+        let operation = Operation::Delete { item: item.clone() };
+
+        assert_eq!(
+            board.edit(SOURCE, &operation),
+            Err(EditError::NoSuchItem(item))
+        );
+    }
+
+    fn random_list(rng: &mut impl Rng, out: &mut String, counter: &mut usize, indent: usize) {
+        let ordered = rng.random_bool(0.3);
+        let bullet = *["-", "*", "+"].choose(rng).unwrap();
+        let delimiter = *[".", ")"].choose(rng).unwrap();
+        // Only lists that start at 1 can interrupt the text of their parent item
+        let start = if indent == 0 {
+            rng.random_range(0..3)
+        } else {
+            1
+        };
+        let lazy = rng.random_bool(0.3);
+        let loose = rng.random_bool(0.2);
+        let len = rng.random_range(1..5);
+
+        for i in 0..len {
+            let marker = match (ordered, lazy) {
+                (false, _) => bullet.to_string(),
+                (true, true) => format!("{start}{delimiter}"),
+                (true, false) => format!("{}{delimiter}", start + i),
+            };
+            let checkbox = *["", "[ ] ", "[x] ", "[X] "].choose(rng).unwrap();
+            let metadata = *["", "", " #tag", " due:2026-01-02", " @bob #tag !low"]
+                .choose(rng)
+                .unwrap();
+            *counter += 1;
+            out.push_str(&format!(
+                "{:indent$}{marker} {checkbox}task {counter}{metadata}\n",
+                ""
+            ));
+            if rng.random_bool(0.2) {
+                let width = indent + marker.len() + 1 + checkbox.len();
+                out.push_str(&format!("{:width$}continued {counter}\n", ""));
+            }
+            if indent < 6 && rng.random_bool(0.3) {
+                random_list(rng, out, counter, indent + marker.len() + 1);
+            }
+            if loose && i + 1 < len {
+                out.push('\n');
+            }
+        }
+    }
+
+    fn random_source(rng: &mut impl Rng) -> String {
+        let mut out = String::new();
+        let mut counter = 0;
+
+        if rng.random_bool(0.5) {
+            out.push_str("# Project\n\nSome intro.\n\n");
+        }
+
+        for section in 0..rng.random_range(1..4) {
+            out.push_str(&format!("## Section {section}\n\n"));
+            for list in 0..rng.random_range(0..3) {
+                if list > 0 || rng.random_bool(0.3) {
+                    out.push_str("Some prose.\n\n");
+                }
+                random_list(rng, &mut out, &mut counter, 0);
+                out.push('\n');
+            }
+        }
+
+        if rng.random_bool(0.3) {
+            out.truncate(out.trim_end().len());
+        }
+
+        out
+    }
+
+    fn random_list_path(rng: &mut impl Rng, board: &Board) -> (ListPath, usize) {
+        let items = board.items();
+
+        if items.is_empty() || rng.random_bool(0.1) {
+            let section = rng.random_range(0..board.sections.len());
+            return (ListPath::new(section, 0), 0);
+        }
+
+        let (path, item) = items.choose(rng).unwrap();
+        if rng.random_bool(0.2) && item.element.nested_list.is_none() {
+            return (path.nested(), 0);
+        }
+
+        let len = board.list(&path.list).unwrap().element.items.len();
+        (path.list.clone(), rng.random_range(0..=len))
+    }
+
+    /// The text with a few words replaced, removed or added, and maybe a line break
+    fn random_text_change(rng: &mut impl Rng, text: &str) -> String {
+        let mut words: Vec<String> = text.split(' ').map(String::from).collect();
+
+        for _ in 0..rng.random_range(1..3) {
+            let index = rng.random_range(0..words.len());
+            match rng.random_range(0..4) {
+                0 => words[index] = String::from("changed"),
+                1 if words.len() > 1 => {
+                    words.remove(index);
+                }
+                2 => words.insert(index, String::from("new\nline")),
+                _ => words.insert(index, String::from("*added*")),
+            }
+        }
+
+        words.join(" ")
+    }
+
+    fn random_operation(rng: &mut impl Rng, source: &str, board: &Board) -> Option<Operation> {
+        let items = board.items();
+        let (item, span) = items.choose(rng)?.clone();
+        let (list, index) = random_list_path(rng, board);
+
+        let tag = String::from(*["tag", "new"].choose(rng).unwrap());
+
+        let operation = match rng.random_range(0..10) {
+            0 => Operation::SetChecked {
+                item,
+                checked: rng.random_bool(0.5),
+            },
+            5 => Operation::AddCheckbox {
+                item,
+                checked: rng.random_bool(0.5),
+            },
+            1 => Operation::Rename {
+                item,
+                text: String::from("renamed"),
+            },
+            2 => Operation::Insert {
+                list,
+                index,
+                text: String::from("inserted"),
+                checkbox: [None, Some(false), Some(true)]
+                    .choose(rng)
+                    .copied()
+                    .unwrap(),
+            },
+            3 => Operation::Delete { item },
+            6 => Operation::SetDue {
+                item,
+                due: [None, "2026-01-02".parse().ok(), "2027-12-31".parse().ok()]
+                    .choose(rng)
+                    .copied()
+                    .unwrap(),
+            },
+            7 => Operation::AddTag { item, tag },
+            8 => Operation::RemoveTag { item, tag },
+            9 => Operation::ReplaceText {
+                text: random_text_change(rng, &span.editable_text(source)),
+                item,
+            },
+            _ => Operation::Move { item, list, index },
+        };
+
+        Some(operation)
+    }
+
+    /// The item text, with whitespace normalized
+    fn text(source: &str, item: &Span<Item>) -> String {
+        item.text(source)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn count(list: Option<&Span<List>>) -> usize {
+        list.map_or(0, |list| {
+            list.element
+                .items
+                .iter()
+                .map(|item| 1 + count(item.element.nested_list.as_ref()))
+                .sum()
+        })
+    }
+
+    /// Checks that the new source is the old one with the splices applied, and nothing else
+    fn assert_preserved(source: &str, edit: &Edit, new: &str) {
+        let mut old_pos = 0;
+        let mut new_pos = 0;
+
+        for splice in edit.splices() {
+            let kept = splice.range.start - old_pos;
+            assert_eq!(
+                &source[old_pos..splice.range.start],
+                &new[new_pos..new_pos + kept]
+            );
+            new_pos += kept;
+            assert_eq!(&new[new_pos..new_pos + splice.text.len()], splice.text);
+            new_pos += splice.text.len();
+            old_pos = splice.range.end;
+        }
+
+        assert_eq!(&source[old_pos..], &new[new_pos..]);
+    }
+
+    /// The lines of `source` that aren't part of `item` or of its nested list. Blank lines are
+    /// left out, since in loose lists the blank line between items moves along with them.
+    fn lines_around<'a>(source: &'a str, item: &Span<Item>) -> Vec<&'a str> {
+        let start = item_line_start(source, item);
+        let end = trim_blank_lines(source, item.range.clone()).end;
+
+        source[..start]
+            .lines()
+            .chain(source[end..].lines())
+            .filter(|line| !line.trim().is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn random_edits_preserve_the_rest_of_the_source() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..2000 {
+            let source = random_source(&mut rng);
+            let (board, _) = Board::parse(&source);
+            let Some(operation) = random_operation(&mut rng, &source, &board) else {
+                continue;
+            };
+
+            let edit = match board.edit(&source, &operation) {
+                Ok(edit) => edit,
+                Err(
+                    EditError::MoveIntoItself(_)
+                    | EditError::NoCheckbox(_)
+                    | EditError::HasCheckbox(_),
+                ) => continue,
+                Err(e) => panic!("{e} in {source:?}, {operation:?}"),
+            };
+            let new = edit.apply(&source);
+            assert_preserved(&source, &edit, &new);
+            assert_eq!(edit.inverse(&source).apply(&new), source, "{operation:?}");
+
+            // The same edit on the file with CRLF line endings or a byte order mark
+            let crlf = source.replace('\n', "\r\n");
+            let (crlf_new, _) = Board::parse(&crlf).0.apply(&crlf, &operation).unwrap();
+            assert_eq!(crlf_new, new.replace('\n', "\r\n"), "{operation:?}");
+            let bom = format!("\u{feff}{source}");
+            let (bom_new, _) = Board::parse(&bom).0.apply(&bom, &operation).unwrap();
+            assert_eq!(bom_new, format!("\u{feff}{new}"), "{operation:?}");
+
+            let (new_board, _) = Board::parse(&new);
+            let total = count_all(&board);
+            let new_total = count_all(&new_board);
+
+            match &operation {
+                Operation::SetChecked { item, checked }
+                | Operation::AddCheckbox { item, checked } => {
+                    let new_item = new_board.item(item).unwrap();
+                    assert_eq!(
+                        new_item.element.checkbox.as_ref().map(|c| c.element),
+                        Some(*checked)
+                    );
+                    assert_eq!(new_total, total);
+                }
+                Operation::Rename { item, .. } => {
+                    assert_eq!(text(&new, new_board.item(item).unwrap()), "renamed");
+                    assert_eq!(new_total, total);
+                }
+                Operation::ReplaceText { item, text } => {
+                    let new_item = new_board.item(item).unwrap();
+                    assert_eq!(
+                        new_item.editable_text(&new),
+                        *text,
+                        "{source:?} -> {new:?}, {operation:?}"
+                    );
+                    assert_eq!(new_total, total);
+                }
+                Operation::Insert {
+                    list,
+                    index,
+                    checkbox,
+                    ..
+                } => {
+                    let new_item = new_board.item(&list.item(*index)).unwrap_or_else(|| {
+                        panic!("nothing inserted in {source:?} -> {new:?}, {operation:?}")
+                    });
+                    assert_eq!(text(&new, new_item), "inserted");
+                    assert_eq!(
+                        new_item.element.checkbox.as_ref().map(|c| c.element),
+                        *checkbox
+                    );
+                    assert_eq!(new_total, total + 1);
+                }
+                Operation::Delete { item } => {
+                    let removed = 1 + count(board.item(item).unwrap().element.nested_list.as_ref());
+                    assert_eq!(new_total, total - removed, "{source:?} -> {new:?}");
+                }
+                Operation::Move { item, list, .. } => {
+                    let old = board.item(item).unwrap();
+                    let name = text(&source, old);
+                    let found: Vec<_> = new_board
+                        .items()
+                        .into_iter()
+                        .filter(|(_, i)| text(&new, i) == name)
+                        .collect();
+                    assert_eq!(found.len(), 1, "{source:?} -> {new:?}, {operation:?}");
+                    assert_eq!(
+                        count(found[0].1.element.nested_list.as_ref()),
+                        count(old.element.nested_list.as_ref()),
+                        "{source:?} -> {new:?}, {operation:?}"
+                    );
+                    assert_eq!(new_total, total, "{source:?} -> {new:?}, {operation:?}");
+
+                    // Reordering a bullet list is a cut and paste of the moved lines
+                    let bullets = board
+                        .list(list)
+                        .is_some_and(|l| matches!(l.element.kind, ListKind::Bullet(_)));
+                    if *list == item.list && bullets {
+                        assert_eq!(new.lines().count(), source.lines().count());
+                        assert_eq!(
+                            lines_around(&new, found[0].1),
+                            lines_around(&source, old),
+                            "{source:?} -> {new:?}, {operation:?}"
+                        );
+                    }
+                }
+                Operation::SetDue { item, due } => {
+                    let metadata = &new_board.item(item).unwrap().element.metadata;
+                    assert_eq!(metadata.due.as_ref().map(|d| d.element), *due);
+                    assert_eq!(new_total, total);
+                }
+                Operation::AddTag { item, tag } | Operation::RemoveTag { item, tag } => {
+                    let metadata = &new_board.item(item).unwrap().element.metadata;
+                    assert_eq!(
+                        metadata.tags.iter().any(|t| &t.element == tag),
+                        matches!(operation, Operation::AddTag { .. }),
+                        "{source:?} -> {new:?}, {operation:?}"
+                    );
+                    assert_eq!(new_total, total);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn count_all(board: &Board) -> usize {
+        board.items().len()
+    }
+}
@@ -0,0 +1,14 @@
+//! Converting boards from and to the formats of other todo apps.
+//!
+//! Imports give markdown in the todomd layout, to be saved as a new TODO.md or parsed with
+//! [`Board::parse`](crate::Board::parse). Exports work from a parsed board and its source.
+
+mod card;
+#[cfg(feature = "serde")]
+pub mod github;
+pub mod ics;
+#[cfg(feature = "serde")]
+pub mod nullboard;
+pub mod todotxt;
+#[cfg(feature = "serde")]
+pub mod trello;
@@ -0,0 +1,149 @@
+//! Items written by the importers, from the cards, notes and issues of other apps. Their text is
+//! escaped so that it stays text of the item, whatever markdown it looks like.
+
+use chrono::{DateTime, NaiveDate};
+
+use crate::{Field, Grammar, Inline};
+
+/// Characters that can start a markdown block at the beginning of a line
+pub(super) const BLOCK_STARTS: &[char] = &['#', '>', '-', '*', '+', '=', '_', '<', '`', '~', '\\'];
+
+/// Escapes the start of a line of text, so that it's read as text of an item
+pub(super) fn escape_line(line: &str) -> String {
+    // Leading spaces would be taken as indentation
+    let line = line.trim_start();
+
+    if line.starts_with(BLOCK_STARTS) {
+        format!("\\{line}")
+    } else if let Some(digits) = ordered_marker(line) {
+        format!("{}\\{}", &line[..digits], &line[digits..])
+    } else {
+        line.to_string()
+    }
+}
+
+/// The number of digits of an ordered list marker at the start of `line`, like `12.` or `3)`
+fn ordered_marker(line: &str) -> Option<usize> {
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    ((1..=9).contains(&digits) && line[digits..].starts_with(['.', ')'])).then_some(digits)
+}
+
+pub(super) fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A label of another app as a tag: spaces become `-`, and characters that tags can't have are
+/// dropped. `None` if nothing is left, or only digits, which would be an issue reference.
+pub(super) fn tag(label: &str) -> Option<String> {
+    let tag: String = label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'))
+        .collect();
+
+    let tag = tag.trim_matches('-');
+
+    (!tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit())).then(|| tag.to_string())
+}
+
+/// The date of a timestamp like `2026-03-01T12:00:00.000Z`
+pub(super) fn date(timestamp: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|time| time.date_naive())
+}
+
+/// A card, note or issue of another app, as an item to write
+#[derive(Debug, Default)]
+pub(super) struct Card {
+    pub checkbox: Option<bool>,
+    /// The first line goes after the checkbox, and the others are continuation lines
+    pub text: String,
+    /// Written after the first line, with the [`Inline`] grammar
+    pub fields: Vec<Field>,
+    pub nested: Vec<Card>,
+}
+
+impl Card {
+    pub fn new(checked: bool, text: impl Into<String>) -> Self {
+        Card {
+            checkbox: Some(checked),
+            text: text.into(),
+            ..Card::default()
+        }
+    }
+
+    /// The card as a markdown item, with its nested cards
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let marker = match self.checkbox {
+            Some(true) => "- [x]",
+            Some(false) => "- [ ]",
+            None => "-",
+        };
+        let continuation = " ".repeat(indent.len() + marker.len() + 1);
+
+        out.push_str(&indent);
+        out.push_str(marker);
+
+        let mut lines = self.text.trim_end().lines();
+        let first = lines.next().map(escape_line).unwrap_or_default();
+        let fields = self.fields.iter().filter_map(|field| Inline.write(field));
+        for word in std::iter::once(first).chain(fields) {
+            if !word.is_empty() {
+                out.push(' ');
+                out.push_str(&word);
+            }
+        }
+        for line in lines.map(escape_line) {
+            out.push('\n');
+            if !line.is_empty() {
+                out.push_str(&continuation);
+                out.push_str(&line);
+            }
+        }
+        out.push('\n');
+
+        for card in &self.nested {
+            card.write(out, depth + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards() {
+        let mut card = Card::new(false, "Groceries\n- milk\n\n2. eggs");
+        card.fields = vec![
+            Field::Tag("home".into()),
+            Field::Due(NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()),
+        ];
+        card.nested = vec![Card::new(true, "list"), Card::new(false, "# shop")];
+
+        assert_eq!(
+            card.to_markdown(),
+            "\
+- [ ] Groceries #home due:2026-03-01
+      \\- milk
+
+      2\\. eggs
+  - [x] list
+  - [ ] \\# shop
+"
+        );
+        assert_eq!(tag("good first issue"), Some("good-first-issue".into()));
+        assert_eq!(tag("bug 🐛!"), Some("bug".into()));
+        assert_eq!(tag("2026"), None);
+    }
+}
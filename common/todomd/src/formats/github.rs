@@ -0,0 +1,308 @@
+//! GitHub issues and projects, as printed by the `gh` command line tool. Needs the `serde`
+//! feature.
+//!
+//! Two kinds of files are read: the issue lists of `gh issue list --json
+//! number,title,state,body,labels,assignees,createdAt,closedAt`, and the project items of `gh
+//! project item-list <number> --format json`. Each issue or project item becomes an item with its
+//! title and `#number`, its labels as tags and its assignees as assignees. The task list of its
+//! body, like `- [x] write tests`, becomes nested items; the rest of the body is left out.
+//!
+//! Issues are checked when they are closed, and get their dates as `created:` and `done:` fields.
+//! They all go in a single list, in the order of the file. Project items go in a section for
+//! each value of their status field, in order of first appearance, and items without a status go
+//! first, before any heading. Project items are checked when their status is `Done`, or when
+//! their issue is closed. There is no export.
+
+use std::fmt::Write;
+
+use serde::Deserialize;
+
+use super::card::{self, Card, one_line};
+use crate::Field;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Export {
+    Issues(Vec<Issue>),
+    Project(Project),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Issue {
+    #[serde(default)]
+    pub number: Option<u64>,
+    #[serde(default)]
+    pub title: String,
+    /// `OPEN` or `CLOSED`
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub assignees: Vec<User>,
+    /// A timestamp like `2026-03-01T12:00:00Z`
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub closed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct User {
+    pub login: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Project {
+    pub items: Vec<ProjectItem>,
+}
+
+/// An item of a project. The fields of the project are keys of the item, named in lowercase, like
+/// `status`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProjectItem {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    /// The issue, pull request or draft issue of the item
+    #[serde(default)]
+    pub content: Option<Content>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Content {
+    /// `None` for draft issues
+    #[serde(default)]
+    pub number: Option<u64>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub state: Option<String>,
+}
+
+/// The task list items of an issue body
+fn checklist(body: &str) -> Vec<Card> {
+    body.lines()
+        .filter_map(|line| {
+            let rest = line.trim_start().strip_prefix(['-', '*', '+'])?;
+            let rest = rest.strip_prefix(' ')?.trim_start();
+            let (checked, text) = match rest.get(..3)? {
+                "[ ]" => (false, &rest[3..]),
+                "[x]" | "[X]" => (true, &rest[3..]),
+                _ => return None,
+            };
+            let text = one_line(text);
+            (!text.is_empty()).then(|| Card::new(checked, text))
+        })
+        .collect()
+}
+
+/// An issue or project item as an item
+fn issue_card(
+    checked: bool,
+    title: &str,
+    number: Option<u64>,
+    body: &str,
+    labels: impl IntoIterator<Item = impl AsRef<str>>,
+    assignees: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Card {
+    let mut text = one_line(title);
+    if let Some(number) = number {
+        write!(text, " #{number}").unwrap();
+    }
+
+    let tags = labels
+        .into_iter()
+        .filter_map(|label| card::tag(label.as_ref()))
+        .map(Field::Tag);
+    let assignees = assignees
+        .into_iter()
+        .map(|login| Field::Assignee(login.as_ref().to_string()));
+
+    Card {
+        fields: tags.chain(assignees).collect(),
+        nested: checklist(body),
+        ..Card::new(checked, text)
+    }
+}
+
+impl Export {
+    pub fn from_json(json: &str) -> Result<Export, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The issues or project items as markdown in the todomd layout
+    pub fn to_markdown(&self) -> String {
+        let mut sections: Vec<(Option<&str>, Vec<Card>)> = vec![(None, vec![])];
+
+        match self {
+            Export::Issues(issues) => {
+                for issue in issues {
+                    let mut card = issue_card(
+                        issue.state.eq_ignore_ascii_case("closed"),
+                        &issue.title,
+                        issue.number,
+                        &issue.body,
+                        issue.labels.iter().map(|l| &l.name),
+                        issue.assignees.iter().map(|a| &a.login),
+                    );
+                    let date =
+                        |timestamp: &Option<String>| timestamp.as_deref().and_then(card::date);
+                    card.fields
+                        .extend(date(&issue.created_at).map(Field::Created));
+                    card.fields
+                        .extend(date(&issue.closed_at).map(Field::Completed));
+                    sections[0].1.push(card);
+                }
+            }
+            Export::Project(project) => {
+                for item in &project.items {
+                    let content = item.content.as_ref();
+                    let status = item.status.as_deref().map(str::trim);
+                    let closed = content
+                        .and_then(|c| c.state.as_deref())
+                        .is_some_and(|state| state.eq_ignore_ascii_case("closed"));
+                    let card = issue_card(
+                        closed || status.is_some_and(|s| s.eq_ignore_ascii_case("done")),
+                        &item.title,
+                        content.and_then(|c| c.number),
+                        content.map_or("", |c| &c.body),
+                        &item.labels,
+                        &item.assignees,
+                    );
+
+                    match sections.iter_mut().find(|(name, _)| *name == status) {
+                        Some((_, cards)) => cards.push(card),
+                        None => sections.push((status, vec![card])),
+                    }
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for (status, cards) in &sections {
+            if let Some(status) = status {
+                writeln!(out, "## {}\n", one_line(status)).unwrap();
+            }
+            for card in cards {
+                out.push_str(&card.to_markdown());
+            }
+            if !cards.is_empty() {
+                out.push('\n');
+            }
+        }
+
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+
+    #[test]
+    fn import_issues() {
+        let json = r#"[
+  {
+    "number": 12, "title": "Login fails on Safari", "state": "OPEN",
+    "body": "Steps:\n1. open the page\n\n- [x] reproduce\n- [ ] fix\n  * [ ] add a test",
+    "labels": [{ "id": "LA_1", "name": "bug", "color": "d73a4a" },
+               { "id": "LA_2", "name": "good first issue", "color": "7057ff" }],
+    "assignees": [{ "id": "U_1", "login": "octocat", "name": "The Octocat" }],
+    "createdAt": "2026-01-10T09:30:00Z", "closedAt": null, "url": "https://github.com/o/r/issues/12"
+  },
+  {
+    "number": 9, "title": "Update the README", "state": "CLOSED", "body": "",
+    "labels": [], "assignees": [],
+    "createdAt": "2026-01-02T10:00:00Z", "closedAt": "2026-01-05T16:00:00Z"
+  }
+]"#;
+        let markdown = Export::from_json(json).unwrap().to_markdown();
+
+        assert_eq!(
+            markdown,
+            "\
+- [ ] Login fails on Safari #12 #bug #good-first-issue @octocat created:2026-01-10
+  - [x] reproduce
+  - [ ] fix
+  - [ ] add a test
+- [x] Update the README #9 created:2026-01-02 done:2026-01-05
+"
+        );
+
+        let (board, _) = Board::parse(&markdown);
+        let (_, login) = &board.items()[0];
+        let tags: Vec<_> = login
+            .element
+            .metadata
+            .tags
+            .iter()
+            .map(|t| &*t.element)
+            .collect();
+        assert_eq!(
+            tags,
+            ["bug", "good-first-issue"],
+            "issue numbers are not tags"
+        );
+    }
+
+    #[test]
+    fn import_project() {
+        let json = r#"{
+  "items": [
+    {
+      "id": "PVTI_1", "title": "Design the API", "status": "In Progress",
+      "labels": ["api"], "assignees": ["alice"], "repository": "https://github.com/o/r",
+      "content": { "type": "Issue", "number": 3, "title": "Design the API", "body": "- [ ] draft" }
+    },
+    {
+      "id": "PVTI_2", "title": "Pick a name",
+      "content": { "type": "DraftIssue", "title": "Pick a name", "body": "" }
+    },
+    {
+      "id": "PVTI_3", "title": "Set up CI", "status": "Done",
+      "content": { "type": "Issue", "number": 1, "title": "Set up CI", "body": "" }
+    },
+    {
+      "id": "PVTI_4", "title": "Write docs", "status": "In Progress",
+      "content": { "type": "PullRequest", "number": 4, "title": "Write docs", "body": "" }
+    }
+  ],
+  "totalCount": 4
+}"#;
+        let markdown = Export::from_json(json).unwrap().to_markdown();
+
+        assert_eq!(
+            markdown,
+            "\
+- [ ] Pick a name
+
+## In Progress
+
+- [ ] Design the API #3 #api @alice
+  - [ ] draft
+- [ ] Write docs #4
+
+## Done
+
+- [x] Set up CI #1
+"
+        );
+    }
+}
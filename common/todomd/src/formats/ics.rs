@@ -0,0 +1,250 @@
+//! [iCalendar](https://www.rfc-editor.org/rfc/rfc5545) files of to-dos, for calendar apps.
+//!
+//! On export, each item with a due date becomes a `VTODO`, nested items included: its text
+//! without metadata is the `SUMMARY`, its tags are `CATEGORIES`, and its checkbox gives the
+//! `STATUS`. The `UID` comes from the [id](crate::ItemId) of the item, so calendar apps see the
+//! same to-do each time the file is exported, as long as its text doesn't change: its due date,
+//! tags and other fields can. Items that
+//! get renamed keep their to-do when they have a `^anchor`. There is no import.
+
+use std::fmt::Write;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{Board, Priority};
+
+/// Escapes a text value: backslashes, `;`, `,` and newlines
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a content line, folded so that no line is longer than 75 bytes
+fn line(out: &mut String, content: &str) {
+    let mut width = 0;
+    for c in content.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// A date as a time in UTC, for the properties that can't be plain dates
+fn date_time(date: NaiveDate) -> String {
+    date.format("%Y%m%dT000000Z").to_string()
+}
+
+/// `1` is the highest priority, and `9` the lowest
+fn priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::Highest => 1,
+        Priority::High => 3,
+        Priority::Medium => 5,
+        Priority::Low => 7,
+        Priority::Lowest => 9,
+    }
+}
+
+impl Board {
+    /// The items with a due date, as an iCalendar file of to-dos. The `UID` of each to-do is its
+    /// item id at `uid_domain`, which should tell this board apart from others, like the name of
+    /// its project. `stamp` is the time the file is written.
+    pub fn to_ics(&self, source: &str, uid_domain: &str, stamp: DateTime<Utc>) -> String {
+        let mut out = String::new();
+        let stamp = stamp.format("%Y%m%dT%H%M%SZ");
+
+        line(&mut out, "BEGIN:VCALENDAR");
+        line(&mut out, "VERSION:2.0");
+        line(&mut out, "PRODID:-//todomd//todomd//EN");
+
+        for ((_, item), (_, id)) in self.items().into_iter().zip(self.ids(source)) {
+            let metadata = &item.element.metadata;
+            let Some(due) = &metadata.due else {
+                continue;
+            };
+            let done = item.element.checkbox.as_ref().is_some_and(|c| c.element);
+            let id = id.to_string();
+
+            line(&mut out, "BEGIN:VTODO");
+            line(
+                &mut out,
+                &format!("UID:{}@{uid_domain}", id.trim_start_matches('^')),
+            );
+            line(&mut out, &format!("DTSTAMP:{stamp}"));
+            line(
+                &mut out,
+                &format!("SUMMARY:{}", escape(&item.text_without_fields(source))),
+            );
+            line(&mut out, &format!("DUE;VALUE=DATE:{}", date(due.element)));
+            if done {
+                line(&mut out, "STATUS:COMPLETED");
+            } else {
+                line(&mut out, "STATUS:NEEDS-ACTION");
+            }
+            if let Some(completed) = &metadata.completed {
+                line(
+                    &mut out,
+                    &format!("COMPLETED:{}", date_time(completed.element)),
+                );
+            }
+            if let Some(created) = &metadata.created {
+                line(&mut out, &format!("CREATED:{}", date_time(created.element)));
+            }
+            if let Some(p) = &metadata.priority {
+                line(&mut out, &format!("PRIORITY:{}", priority(p.element)));
+            }
+            if !metadata.tags.is_empty() {
+                let mut categories = String::from("CATEGORIES:");
+                for (i, tag) in metadata.tags.iter().enumerate() {
+                    if i > 0 {
+                        categories.push(',');
+                    }
+                    write!(categories, "{}", escape(&tag.element)).unwrap();
+                }
+                line(&mut out, &categories);
+            }
+            line(&mut out, "END:VTODO");
+        }
+
+        line(&mut out, "END:VCALENDAR");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ItemPath, Operation};
+
+    fn stamp() -> DateTime<Utc> {
+        "2026-10-18T09:30:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn export() {
+        let source = "\
+## Todo
+
+- [ ] file taxes, then relax #home #money !high due:2026-04-15 created:2026-01-02
+  - [x] gather receipts due:2026-04-01 done:2026-03-20
+- [ ] no due date #home
+- [ ] renew passport due:2026-06-30 ^passport
+";
+        let (board, _) = Board::parse(source);
+        let ics = board.to_ics(source, "example.org", stamp());
+
+        assert!(ics.lines().all(|line| line.len() <= 75 + 1));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        let unfolded = ics.replace("\r\n ", "");
+        let lines: Vec<_> = unfolded.split("\r\n").collect();
+
+        assert_eq!(lines.iter().filter(|l| **l == "BEGIN:VTODO").count(), 3);
+        assert!(lines.contains(&"SUMMARY:file taxes\\, then relax"));
+        assert!(lines.contains(&"DUE;VALUE=DATE:20260415"));
+        assert!(lines.contains(&"PRIORITY:3"));
+        assert!(lines.contains(&"CATEGORIES:home,money"));
+        assert!(lines.contains(&"CREATED:20260102T000000Z"));
+        assert!(lines.contains(&"DTSTAMP:20261018T093000Z"));
+        assert!(lines.contains(&"SUMMARY:gather receipts"));
+        assert!(lines.contains(&"STATUS:COMPLETED"));
+        assert!(lines.contains(&"COMPLETED:20260320T000000Z"));
+        assert!(lines.contains(&"UID:passport@example.org"));
+        assert!(!unfolded.contains("no due date"));
+    }
+
+    #[test]
+    fn uids_are_stable() {
+        let source = "- [ ] pay rent due:2026-11-01\n- [ ] call mom due:2026-11-02\n";
+        let (board, _) = Board::parse(source);
+        let uids = |ics: &str| -> Vec<String> {
+            ics.lines()
+                .filter_map(|line| line.strip_prefix("UID:"))
+                .map(str::to_string)
+                .collect()
+        };
+        let before = uids(&board.to_ics(source, "home", stamp()));
+
+        // Items are moved and checked
+        let edit = board
+            .edit(
+                source,
+                &Operation::SetChecked {
+                    item: ItemPath::new(0, 0, 1),
+                    checked: true,
+                },
+            )
+            .unwrap();
+        let source = format!("- [ ] new item due:2026-10-30\n{}", edit.apply(source));
+        let (board, _) = Board::parse(&source);
+        let after = uids(&board.to_ics(&source, "home", stamp()));
+
+        assert_eq!(after.len(), 3);
+        assert_eq!(after[1..], before[..]);
+    }
+
+    #[test]
+    fn uids_survive_rescheduling() {
+        let source = "- [ ] pay rent due:2026-11-01\n";
+        let (board, _) = Board::parse(source);
+        let uid = |board: &Board, source: &str| -> String {
+            let ics = board.to_ics(source, "home", stamp());
+            ics.lines()
+                .find_map(|line| line.strip_prefix("UID:"))
+                .unwrap()
+                .to_string()
+        };
+        let before = uid(&board, source);
+
+        let mut source = source.to_string();
+        for operation in [
+            Operation::SetDue {
+                item: ItemPath::new(0, 0, 0),
+                due: NaiveDate::from_ymd_opt(2026, 12, 1),
+            },
+            Operation::AddTag {
+                item: ItemPath::new(0, 0, 0),
+                tag: "money".into(),
+            },
+        ] {
+            let (board, _) = Board::parse(&source);
+            source = board.apply(&source, &operation).unwrap().0;
+        }
+        assert_eq!(source, "- [ ] pay rent due:2026-12-01 #money\n");
+
+        let (board, _) = Board::parse(&source);
+        assert_eq!(uid(&board, &source), before);
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let mut out = String::new();
+        line(&mut out, &format!("SUMMARY:{}", "é".repeat(40)));
+
+        let lines: Vec<_> = out.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert_eq!(
+            out.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "é".repeat(40))
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! [Nullboard](https://nullboard.io) boards, as saved by its Export button. Needs the `serde`
+//! feature.
+//!
+//! A nullboard board is a title and lists of notes. On import, the title becomes a `#` heading,
+//! each list a `##` section, and each note an unchecked item. Lines of notes that would start a
+//! markdown block, like `- ` or `# `, are escaped with a backslash so that they stay text of the
+//! item. On export, each section with a heading at the section level becomes a list, and each
+//! top-level item a note, with its nested items as `- ` lines. Nullboard has no checkboxes, so
+//! checked and unchecked items give the same notes.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use super::card::{BLOCK_STARTS, Card, one_line};
+use crate::{Board, Item, Span};
+
+/// The version of the format written by nullboard, which it checks on import
+pub const FORMAT: u64 = 20190412;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Export {
+    pub format: u64,
+    /// Nullboard uses the time the board was created, in milliseconds
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub revision: u64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub lists: Vec<List>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct List {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    pub text: String,
+    /// Shown without formatting in nullboard
+    #[serde(default)]
+    pub raw: bool,
+    /// Collapsed to its first line in nullboard
+    #[serde(default)]
+    pub min: bool,
+}
+
+impl Note {
+    fn new(text: String) -> Self {
+        Note {
+            text,
+            raw: false,
+            min: false,
+        }
+    }
+}
+
+/// Undoes the escaping of a line by [`Card`]
+fn unescape_line(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix('\\')
+        && rest.starts_with(BLOCK_STARTS)
+    {
+        rest
+    } else {
+        line
+    }
+}
+
+/// Undoes the escaping of an ordered list marker by [`Card`]
+fn unescape_ordered_marker(line: &str) -> String {
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    match line[digits..].strip_prefix('\\') {
+        Some(rest) if (1..=9).contains(&digits) && rest.starts_with(['.', ')']) => {
+            format!("{}{rest}", &line[..digits])
+        }
+        _ => line.to_string(),
+    }
+}
+
+impl Export {
+    pub fn from_json(json: &str) -> Result<Export, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("nullboard exports can be serialized")
+    }
+
+    /// The board as markdown in the todomd layout
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+
+        let title = self.title.trim();
+        if !title.is_empty() {
+            writeln!(out, "# {title}\n").unwrap();
+        }
+
+        for list in &self.lists {
+            writeln!(out, "## {}\n", one_line(&list.title)).unwrap();
+            for note in &list.notes {
+                out.push_str(&Card::new(false, &note.text).to_markdown());
+            }
+            if !list.notes.is_empty() {
+                out.push('\n');
+            }
+        }
+
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        out
+    }
+}
+
+/// The text of a note: the lines of the item, then its nested items as `- ` lines
+fn note_text(source: &str, item: &Span<Item>) -> String {
+    let mut lines: Vec<String> = item
+        .editable_text(source)
+        .lines()
+        .map(|line| unescape_ordered_marker(unescape_line(line)))
+        .collect();
+
+    let mut nested = vec![(0, item)];
+    while let Some((depth, item)) = nested.pop() {
+        if depth > 0 {
+            let checkbox = match item.element.checkbox.as_ref().map(|c| c.element) {
+                Some(true) => "[x] ",
+                Some(false) => "[ ] ",
+                None => "",
+            };
+            lines.push(format!(
+                "{}- {checkbox}{}",
+                "  ".repeat(depth - 1),
+                one_line(item.text(source))
+            ));
+        }
+        if let Some(list) = &item.element.nested_list {
+            nested.extend(list.element.items.iter().rev().map(|i| (depth + 1, i)));
+        }
+    }
+
+    lines.join("\n")
+}
+
+impl Board {
+    /// The board as a nullboard export. Sections with a heading above the section level give
+    /// the title of the board, the first one that has no lists.
+    pub fn to_nullboard(&self, source: &str) -> Export {
+        let section_level = self.options.section_level.unwrap_or_default();
+        let mut title = None;
+        let mut lists = vec![];
+
+        for section in &self.sections {
+            let heading = section.element.heading.as_ref();
+            let has_items = section
+                .element
+                .lists
+                .iter()
+                .any(|l| !l.element.items.is_empty());
+
+            match heading {
+                Some(heading) if heading.element.level < section_level && !has_items => {
+                    title.get_or_insert_with(|| heading.title(source).trim().to_string());
+                }
+                None if !has_items => {}
+                _ => lists.push(List {
+                    title: heading.map_or("", |h| h.title(source).trim()).to_string(),
+                    notes: section
+                        .element
+                        .lists
+                        .iter()
+                        .flat_map(|list| &list.element.items)
+                        .map(|item| Note::new(note_text(source, item)))
+                        .collect(),
+                }),
+            }
+        }
+
+        Export {
+            format: FORMAT,
+            id: chrono::Utc::now().timestamp_millis(),
+            revision: 1,
+            title: title.unwrap_or_default(),
+            lists,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Like an export of nullboard's welcome board
+    const SAMPLE: &str = r##"{
+  "format": 20190412,
+  "id": 1729260000000,
+  "revision": 42,
+  "title": "Side project",
+  "lists": [
+    {
+      "title": "To do",
+      "notes": [
+        { "text": "Write the README", "raw": false, "min": false },
+        { "text": "Groceries\n- milk\n- 2 eggs\n\n1. first\n# not a heading", "raw": false, "min": true },
+        { "text": "  indented\n> quoted", "raw": true, "min": false }
+      ]
+    },
+    {
+      "title": "Doing",
+      "notes": [
+        { "text": "Fix the *login* bug #urgent", "raw": false, "min": false }
+      ]
+    },
+    { "title": "Done", "notes": [] }
+  ]
+}"##;
+
+    #[test]
+    fn import() {
+        let export = Export::from_json(SAMPLE).unwrap();
+        let markdown = export.to_markdown();
+
+        assert_eq!(
+            markdown,
+            "\
+# Side project
+
+## To do
+
+- [ ] Write the README
+- [ ] Groceries
+      \\- milk
+      \\- 2 eggs
+
+      1\\. first
+      \\# not a heading
+- [ ] indented
+      \\> quoted
+
+## Doing
+
+- [ ] Fix the *login* bug #urgent
+
+## Done
+"
+        );
+
+        let (board, _) = Board::parse(&markdown);
+        let titles: Vec<_> = board.sections.iter().map(|s| s.title(&markdown)).collect();
+        assert_eq!(
+            titles,
+            [
+                Some("Side project"),
+                Some("To do"),
+                Some("Doing"),
+                Some("Done")
+            ]
+        );
+        assert_eq!(board.sections[1].element.lists[0].element.items.len(), 3);
+    }
+
+    #[test]
+    fn round_trip_from_nullboard() {
+        let export = Export::from_json(SAMPLE).unwrap();
+        let markdown = export.to_markdown();
+        let (board, _) = Board::parse(&markdown);
+        let back = board.to_nullboard(&markdown);
+
+        assert_eq!(back.title, export.title);
+        let texts = |export: &Export| -> Vec<(String, Vec<String>)> {
+            export
+                .lists
+                .iter()
+                .map(|l| {
+                    let notes = l.notes.iter().map(|n| n.text.clone()).collect();
+                    (l.title.clone(), notes)
+                })
+                .collect()
+        };
+        let mut expected = texts(&export);
+        // Leading spaces are not kept
+        expected[0].1[2] = "indented\n> quoted".into();
+        assert_eq!(texts(&back), expected);
+
+        assert_eq!(Export::from_json(&back.to_json()).unwrap(), back);
+    }
+
+    #[test]
+    fn round_trip_from_markdown() {
+        let source = "\
+# Project
+
+## Todo
+
+- [ ] first
+- [ ] second
+      \\- with a line
+
+## Done
+";
+        let (board, _) = Board::parse(source);
+        assert_eq!(board.to_nullboard(source).to_markdown(), source);
+    }
+
+    #[test]
+    fn export_flattens_nested_items() {
+        let source = "## Todo\n\n- [x] parent\n  - [ ] child\n    - grandchild\n- [ ] other\n";
+        let (board, _) = Board::parse(source);
+        let export = board.to_nullboard(source);
+
+        assert_eq!(export.title, "");
+        let notes: Vec<_> = export.lists[0].notes.iter().map(|n| &n.text).collect();
+        assert_eq!(notes, ["parent\n- [ ] child\n  - grandchild", "other"]);
+    }
+}
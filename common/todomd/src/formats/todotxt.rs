@@ -0,0 +1,466 @@
+//! [todo.txt](https://github.com/todotxt/todo.txt) files, one task per line.
+//!
+//! A task is a line like `x 2026-02-20 2026-02-01 (A) call mom +family @phone due:2026-03-01`:
+//! an optional `x` when it's done, then its completion and creation dates, its priority, and its
+//! text with `+project`, `@context` and `key:value` words. On import, each task becomes an item of
+//! the section named by its first project, and tasks without a project go first, before any
+//! heading. The dates become `created:` and `done:` fields and the priority a `!high` one, as
+//! the default [`Inline`] grammar writes them, so the board reads the same in the app and the
+//! CLI. Projects and contexts stay in the text, as `+project` and `@context` words: the
+//! [`TodoTxt`] grammar of [`parse_options`], which exports use, reads them as fields.
+//!
+//! On export, each item gives a task, nested items included, with the fields of its metadata in
+//! a fixed order, so that exporting the same board gives the same file. Items that are not in a
+//! section of their project get the project of their section. Priorities after `D` are all
+//! lowest, which todo.txt writes as `E`.
+
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+
+use super::card::escape_line;
+use crate::{Board, Field, Grammar, Inline, Item, ParseOptions, Priority, Span, TodoTxt};
+
+/// A line of a todo.txt file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub done: bool,
+    /// A letter from `A` to `Z`
+    pub priority: Option<char>,
+    pub completed: Option<NaiveDate>,
+    pub created: Option<NaiveDate>,
+    /// The rest of the line, with its projects, contexts and `key:value` pairs
+    pub text: String,
+}
+
+/// Reads a date at the start of `rest`, and moves `rest` past it
+fn leading_date(rest: &mut &str) -> Option<NaiveDate> {
+    let (word, after) = rest.split_once(' ').unwrap_or((rest, ""));
+    let date = NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()?;
+    *rest = after.trim_start();
+    Some(date)
+}
+
+/// Reads a `(A)` priority at the start of `rest`, and moves `rest` past it
+fn leading_priority(rest: &mut &str) -> Option<char> {
+    let (word, after) = rest.split_once(' ').unwrap_or((rest, ""));
+    let [b'(', letter @ b'A'..=b'Z', b')'] = word.as_bytes() else {
+        return None;
+    };
+    *rest = after.trim_start();
+    Some(*letter as char)
+}
+
+impl Task {
+    /// Reads a line of a todo.txt file. Returns `None` for blank lines.
+    ///
+    /// Done tasks lose their priority in most todo.txt apps, which keep it as a `pri:A` pair
+    /// instead; it's read back as the priority of the task.
+    pub fn parse(line: &str) -> Option<Task> {
+        let mut rest = line.trim();
+        if rest.is_empty() {
+            return None;
+        }
+
+        let done = match rest.strip_prefix("x ") {
+            Some(after) => {
+                rest = after.trim_start();
+                true
+            }
+            None => false,
+        };
+        let mut priority = leading_priority(&mut rest);
+        let (completed, created) = match leading_date(&mut rest) {
+            Some(first) if done => (Some(first), leading_date(&mut rest)),
+            first => (None, first),
+        };
+        if priority.is_none() {
+            priority = leading_priority(&mut rest);
+        }
+
+        let mut words: Vec<&str> = rest.split_whitespace().collect();
+        if done && priority.is_none() {
+            let pri = words
+                .iter()
+                .position(|word| matches!(word.as_bytes(), [b'p', b'r', b'i', b':', b'A'..=b'Z']));
+            if let Some(index) = pri {
+                priority = words.remove(index).chars().last();
+            }
+        }
+
+        Some(Task {
+            done,
+            priority,
+            completed,
+            created,
+            text: words.join(" "),
+        })
+    }
+
+    /// The projects of the task, in order
+    pub fn projects(&self) -> impl Iterator<Item = &str> {
+        self.text
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix('+'))
+            .filter(|project| !project.is_empty())
+    }
+
+    /// The task as a markdown item, with its priority and dates as fields of the [`Inline`]
+    /// grammar. The text is escaped so that it can't start a heading, a quote or a list.
+    pub fn to_markdown(&self) -> String {
+        let mut item = String::from(if self.done { "- [x]" } else { "- [ ]" });
+
+        if !self.text.is_empty() {
+            write!(item, " {}", escape_line(&self.text)).unwrap();
+        }
+        if let Some(priority) = self
+            .priority
+            .and_then(|p| Inline.write(&Field::Priority(priority(p))))
+        {
+            write!(item, " {priority}").unwrap();
+        }
+        if let Some(created) = self.created {
+            write!(item, " created:{created}").unwrap();
+        }
+        if let Some(completed) = self.completed {
+            write!(item, " done:{completed}").unwrap();
+        }
+        item.push('\n');
+
+        item
+    }
+
+    /// The task for an item. `project` is the project of the section the item is in.
+    fn from_item(source: &str, item: &Span<Item>, project: Option<&str>) -> Task {
+        let metadata = &item.element.metadata;
+        let done = item.element.checkbox.as_ref().is_some_and(|c| c.element);
+
+        let text = item.text_without_fields(source);
+        let mut words: Vec<&str> = text.split_whitespace().collect();
+        let projects: Vec<&str> = metadata.projects.iter().map(|p| &*p.element).collect();
+        let mut fields: Vec<Field> = project
+            .filter(|project| !projects.contains(project))
+            .map(|project| Field::Project(project.to_string()))
+            .into_iter()
+            .collect();
+
+        fields.extend(
+            metadata
+                .projects
+                .iter()
+                .map(|p| Field::Project(p.element.clone())),
+        );
+        fields.extend(
+            metadata
+                .contexts
+                .iter()
+                .map(|c| Field::Context(c.element.clone())),
+        );
+        fields.extend(
+            metadata
+                .assignees
+                .iter()
+                .map(|a| Field::Assignee(a.element.clone())),
+        );
+        fields.extend(metadata.tags.iter().map(|t| Field::Tag(t.element.clone())));
+        fields.extend(metadata.due.as_ref().map(|d| Field::Due(d.element)));
+        fields.extend(metadata.other.iter().map(|pair| {
+            let (key, value) = pair.element.clone();
+            Field::Other { key, value }
+        }));
+
+        let mut completed = metadata.completed.as_ref().map(|d| d.element);
+        let mut created = metadata.created.as_ref().map(|d| d.element);
+        // todo.txt only has a leading completion date on done tasks, and a leading creation date
+        // on done tasks after their completion date
+        if !done && let Some(date) = completed.take() {
+            fields.push(Field::Completed(date));
+        }
+        if done
+            && completed.is_none()
+            && let Some(date) = created.take()
+        {
+            fields.push(Field::Created(date));
+        }
+
+        let written: Vec<String> = fields.iter().filter_map(write_field).collect();
+        words.extend(written.iter().map(String::as_str));
+
+        Task {
+            done,
+            priority: metadata.priority.as_ref().map(|p| letter(p.element)),
+            completed,
+            created,
+            text: words.join(" "),
+        }
+    }
+}
+
+impl fmt::Display for Task {
+    /// The task as a todo.txt line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words = vec![];
+
+        if self.done {
+            words.push("x".to_string());
+        }
+        if let (false, Some(priority)) = (self.done, self.priority) {
+            words.push(format!("({priority})"));
+        }
+        words.extend(self.completed.iter().map(NaiveDate::to_string));
+        words.extend(self.created.iter().map(NaiveDate::to_string));
+        if !self.text.is_empty() {
+            words.push(self.text.clone());
+        }
+        if let (true, Some(priority)) = (self.done, self.priority) {
+            words.push(format!("pri:{priority}"));
+        }
+
+        f.write_str(&words.join(" "))
+    }
+}
+
+/// The letter of a priority, as written by the [`TodoTxt`] grammar
+fn letter(priority: Priority) -> char {
+    TodoTxt
+        .write(&Field::Priority(priority))
+        .and_then(|written| written.chars().nth(1))
+        .expect("todo.txt has a syntax for priorities")
+}
+
+/// The priority of a letter. Letters after `D` are all lowest.
+fn priority(letter: char) -> Priority {
+    Priority::ALL
+        .into_iter()
+        .find(|&p| self::letter(p) == letter)
+        .unwrap_or(Priority::Lowest)
+}
+
+/// A field as a todo.txt word. Assignees and tags have no todo.txt syntax, so they are kept as
+/// `@name` and `#tag`.
+fn write_field(field: &Field) -> Option<String> {
+    TodoTxt.write(field).or_else(|| Inline.write(field))
+}
+
+/// How to parse the markdown given by [`to_markdown`] for an export: with the [`TodoTxt`] grammar
+/// first, for `+project` and `@context` fields, then [`Inline`] for `#tags` and priorities
+pub fn parse_options() -> ParseOptions {
+    ParseOptions {
+        grammars: vec![Arc::new(TodoTxt), Arc::new(Inline)],
+        ..ParseOptions::default()
+    }
+}
+
+/// A todo.txt file as markdown in the todomd layout
+pub fn to_markdown(todo_txt: &str) -> String {
+    let mut sections: Vec<(Option<String>, Vec<Task>)> = vec![(None, vec![])];
+
+    for task in todo_txt.lines().filter_map(Task::parse) {
+        let project = task.projects().next().map(str::to_string);
+        match sections.iter_mut().find(|(name, _)| *name == project) {
+            Some((_, tasks)) => tasks.push(task),
+            None => sections.push((project, vec![task])),
+        }
+    }
+
+    let mut out = String::new();
+    for (project, tasks) in &sections {
+        if let Some(project) = project {
+            writeln!(out, "## {project}\n").unwrap();
+        }
+        for task in tasks {
+            out.push_str(&task.to_markdown());
+        }
+        if !tasks.is_empty() {
+            out.push('\n');
+        }
+    }
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+impl Board {
+    /// The board as a todo.txt file, one line for each item, nested items included. Items get
+    /// the project of their section, named after its heading with spaces turned into `-`.
+    pub fn to_todo_txt(&self, source: &str) -> String {
+        let mut out = String::new();
+
+        for section in &self.sections {
+            let project = section
+                .title(source)
+                .map(|title| title.split_whitespace().collect::<Vec<_>>().join("-"))
+                .filter(|project| !project.is_empty());
+
+            let mut items: Vec<_> = section
+                .element
+                .lists
+                .iter()
+                .rev()
+                .flat_map(|list| list.element.items.iter().rev())
+                .collect();
+            while let Some(item) = items.pop() {
+                if item.text_range(source).is_some() {
+                    let task = Task::from_item(source, item, project.as_deref());
+                    writeln!(out, "{task}").unwrap();
+                }
+                if let Some(nested) = &item.element.nested_list {
+                    items.extend(nested.element.items.iter().rev());
+                }
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+(A) 2026-02-01 call mom +family @phone due:2026-03-01
+x 2026-02-20 2026-02-10 pay rent +home @online pri:B
+2026-01-15 look for a bike
+(C) fix the fence +home +garden
+x 2026-02-02 recycle
+";
+
+    #[test]
+    fn parse_tasks() {
+        let tasks: Vec<_> = SAMPLE.lines().filter_map(Task::parse).collect();
+
+        assert_eq!(tasks.len(), 5);
+        assert_eq!(tasks[0].priority, Some('A'));
+        assert_eq!(tasks[0].created, NaiveDate::from_ymd_opt(2026, 2, 1));
+        assert_eq!(tasks[0].text, "call mom +family @phone due:2026-03-01");
+
+        assert!(tasks[1].done);
+        assert_eq!(tasks[1].priority, Some('B'));
+        assert_eq!(tasks[1].completed, NaiveDate::from_ymd_opt(2026, 2, 20));
+        assert_eq!(tasks[1].created, NaiveDate::from_ymd_opt(2026, 2, 10));
+        assert_eq!(tasks[1].text, "pay rent +home @online");
+
+        assert_eq!(tasks[4].completed, NaiveDate::from_ymd_opt(2026, 2, 2));
+        assert_eq!(tasks[4].created, None);
+
+        assert_eq!(Task::parse("   "), None);
+        for (line, task) in SAMPLE.lines().zip(&tasks) {
+            assert_eq!(task.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn import() {
+        let markdown = to_markdown(SAMPLE);
+        assert_eq!(
+            markdown,
+            "\
+- [ ] look for a bike created:2026-01-15
+- [x] recycle done:2026-02-02
+
+## family
+
+- [ ] call mom +family @phone due:2026-03-01 !highest created:2026-02-01
+
+## home
+
+- [x] pay rent +home @online !high created:2026-02-10 done:2026-02-20
+- [ ] fix the fence +home +garden !medium
+"
+        );
+
+        // The default grammar reads the priority and dates too
+        let (board, _) = Board::parse(&markdown);
+        let (_, call) = &board.items()[2];
+        let metadata = &call.element.metadata;
+        assert_eq!(
+            metadata.priority.as_ref().unwrap().element,
+            Priority::Highest
+        );
+        assert_eq!(
+            metadata.due.as_ref().unwrap().element,
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()
+        );
+
+        let (board, _) = Board::parse_with(&markdown, &parse_options());
+        let (_, call) = &board.items()[2];
+        let metadata = &call.element.metadata;
+        assert_eq!(
+            metadata.priority.as_ref().unwrap().element,
+            Priority::Highest
+        );
+        assert_eq!(metadata.projects[0].element, "family");
+        assert_eq!(metadata.contexts[0].element, "phone");
+        assert_eq!(
+            metadata.created.as_ref().unwrap().element,
+            NaiveDate::from_ymd_opt(2026, 2, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn markdown_is_escaped() {
+        let todo_txt = "# not a heading\n> not a quote\n1. not a list\n- not nested\n";
+        let markdown = to_markdown(todo_txt);
+        let (board, _) = Board::parse(&markdown);
+
+        assert_eq!(
+            markdown,
+            "- [ ] \\# not a heading\n- [ ] \\> not a quote\n- [ ] 1\\. not a list\n- [ ] \\- not nested\n"
+        );
+        let items = board.items();
+        assert_eq!(items.len(), 4);
+        assert!(items.iter().all(|(path, _)| path.depth() == 0));
+        assert!(board.sections.iter().all(|s| s.element.heading.is_none()));
+        assert_eq!(board.to_todo_txt(&markdown).lines().count(), 4);
+    }
+
+    #[test]
+    fn round_trip_from_todo_txt() {
+        let markdown = to_markdown(SAMPLE);
+        let (board, _) = Board::parse_with(&markdown, &parse_options());
+        let exported = board.to_todo_txt(&markdown);
+
+        // Tasks are grouped by project, and keep their words in todo.txt order otherwise
+        assert_eq!(
+            exported,
+            "\
+2026-01-15 look for a bike
+x 2026-02-02 recycle
+(A) 2026-02-01 call mom +family @phone due:2026-03-01
+x 2026-02-20 2026-02-10 pay rent +home @online pri:B
+(C) fix the fence +home +garden
+"
+        );
+        assert_eq!(to_markdown(&exported), markdown);
+    }
+
+    #[test]
+    fn export_from_markdown() {
+        let source = "\
+# Project
+
+## Next week
+
+- [ ] plan the trip !high #travel @bob
+  - [x] book the hotel created:2026-01-02
+- plain item due:2026-05-01
+- [x] sent the invite done:2026-01-03
+";
+        let (board, _) = Board::parse(source);
+        let expected = "\
+(B) plan the trip +Next-week @bob #travel
+x book the hotel +Next-week created:2026-01-02
+plain item +Next-week due:2026-05-01
+x 2026-01-03 sent the invite +Next-week
+";
+        assert_eq!(board.to_todo_txt(source), expected);
+        assert_eq!(
+            board.to_todo_txt(source),
+            expected,
+            "exports are deterministic"
+        );
+    }
+}
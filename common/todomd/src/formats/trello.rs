@@ -0,0 +1,301 @@
+//! [Trello](https://trello.com) boards, as saved by Menu > Print, export and share > Export as
+//! JSON. Needs the `serde` feature.
+//!
+//! On import, the name of the board becomes a `#` heading, each open list a `##` section, and
+//! each card an item, with its description as continuation lines, its labels as tags, its
+//! members as assignees and its due date as a `due:` field. Checklists become nested items; a
+//! card with several checklists gets a plain item for each, with the checklist items under it.
+//! Cards are checked when they are closed, which is what Trello calls archived, or when their due
+//! date is marked complete. Archived lists and their cards are left out. There is no export.
+
+use std::fmt::Write;
+
+use serde::Deserialize;
+
+use super::card::{self, one_line};
+use crate::Field;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Export {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub lists: Vec<List>,
+    #[serde(default)]
+    pub cards: Vec<Card>,
+    #[serde(default)]
+    pub checklists: Vec<Checklist>,
+    #[serde(default)]
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct List {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub closed: bool,
+    #[serde(default)]
+    pub pos: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Card {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub desc: String,
+    pub id_list: String,
+    #[serde(default)]
+    pub closed: bool,
+    #[serde(default)]
+    pub pos: f64,
+    /// A timestamp like `2026-03-01T12:00:00.000Z`
+    #[serde(default)]
+    pub due: Option<String>,
+    #[serde(default)]
+    pub due_complete: bool,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub id_members: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Label {
+    /// Empty for labels that only have a color
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checklist {
+    pub id_card: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub pos: f64,
+    #[serde(default)]
+    pub check_items: Vec<CheckItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CheckItem {
+    #[serde(default)]
+    pub name: String,
+    /// `complete` or `incomplete`
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub pos: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Member {
+    pub id: String,
+    pub username: String,
+}
+
+/// Sorts by position on the board, like Trello shows them
+fn by_pos<T>(items: impl IntoIterator<Item = T>, pos: impl Fn(&T) -> f64) -> Vec<T> {
+    let mut items: Vec<T> = items.into_iter().collect();
+    items.sort_by(|a, b| pos(a).total_cmp(&pos(b)));
+    items
+}
+
+impl Export {
+    pub fn from_json(json: &str) -> Result<Export, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The board as markdown in the todomd layout
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+
+        let name = one_line(&self.name);
+        if !name.is_empty() {
+            writeln!(out, "# {name}\n").unwrap();
+        }
+
+        let lists = by_pos(self.lists.iter().filter(|l| !l.closed), |l| l.pos);
+        for list in lists {
+            writeln!(out, "## {}\n", one_line(&list.name)).unwrap();
+
+            let cards = by_pos(self.cards.iter().filter(|c| c.id_list == list.id), |c| {
+                c.pos
+            });
+            for card in &cards {
+                out.push_str(&self.item(card).to_markdown());
+            }
+            if !cards.is_empty() {
+                out.push('\n');
+            }
+        }
+
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        out
+    }
+
+    /// A card as an item, with its checklists as nested items
+    fn item(&self, card: &Card) -> card::Card {
+        let mut text = one_line(&card.name);
+        let desc = card.desc.trim();
+        if !desc.is_empty() {
+            write!(text, "\n{desc}").unwrap();
+        }
+
+        let labels = card
+            .labels
+            .iter()
+            .filter_map(|label| match label.name.as_str() {
+                "" => label.color.as_deref(),
+                name => Some(name),
+            })
+            .filter_map(card::tag);
+        let members = card.id_members.iter().filter_map(|id| {
+            let member = self.members.iter().find(|m| m.id == *id)?;
+            Some(Field::Assignee(member.username.clone()))
+        });
+        let due = card.due.as_deref().and_then(card::date);
+
+        let checklists = by_pos(
+            self.checklists.iter().filter(|c| c.id_card == card.id),
+            |c| c.pos,
+        );
+        let items = |checklist: &Checklist| -> Vec<card::Card> {
+            by_pos(&checklist.check_items, |i| i.pos)
+                .into_iter()
+                .map(|item| card::Card::new(item.state == "complete", one_line(&item.name)))
+                .collect()
+        };
+        let nested = match &checklists[..] {
+            [checklist] => items(checklist),
+            checklists => checklists
+                .iter()
+                .map(|checklist| card::Card {
+                    checkbox: None,
+                    text: one_line(&checklist.name),
+                    nested: items(checklist),
+                    ..card::Card::default()
+                })
+                .collect(),
+        };
+
+        card::Card {
+            fields: labels
+                .map(Field::Tag)
+                .chain(members)
+                .chain(due.map(Field::Due))
+                .collect(),
+            nested,
+            ..card::Card::new(card.closed || card.due_complete, text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+
+    /// The parts of a Trello export that are read, among many others
+    const SAMPLE: &str = r##"{
+  "id": "64f0c0ffee",
+  "name": "Website  relaunch",
+  "lists": [
+    { "id": "l2", "name": "Doing", "closed": false, "pos": 32768 },
+    { "id": "l1", "name": "To do", "closed": false, "pos": 16384 },
+    { "id": "l3", "name": "Old ideas", "closed": true, "pos": 49152 }
+  ],
+  "cards": [
+    {
+      "id": "c1", "name": "Pick a font", "desc": "Serif?\n- ask Bob", "idList": "l1",
+      "closed": false, "pos": 2, "due": "2026-03-01T12:00:00.000Z", "dueComplete": false,
+      "labels": [{ "name": "design work", "color": "green" }, { "name": "", "color": "red" }],
+      "idMembers": ["m1"], "idChecklists": ["k1"]
+    },
+    {
+      "id": "c2", "name": "Write copy", "desc": "", "idList": "l1", "closed": false, "pos": 1,
+      "due": null, "dueComplete": false, "labels": [], "idMembers": [], "idChecklists": ["k2", "k3"]
+    },
+    {
+      "id": "c3", "name": "Set up hosting", "desc": "", "idList": "l2", "closed": true, "pos": 1,
+      "labels": [], "idMembers": []
+    },
+    { "id": "c4", "name": "Blog", "idList": "l3", "closed": false, "pos": 1 }
+  ],
+  "checklists": [
+    {
+      "id": "k1", "idCard": "c1", "name": "Checklist", "pos": 1,
+      "checkItems": [
+        { "id": "i2", "name": "compare", "state": "incomplete", "pos": 2 },
+        { "id": "i1", "name": "shortlist", "state": "complete", "pos": 1 }
+      ]
+    },
+    {
+      "id": "k3", "idCard": "c2", "name": "Pages", "pos": 2,
+      "checkItems": [{ "id": "i3", "name": "about", "state": "complete", "pos": 1 }]
+    },
+    {
+      "id": "k2", "idCard": "c2", "name": "Review", "pos": 1,
+      "checkItems": [{ "id": "i4", "name": "proofread", "state": "incomplete", "pos": 1 }]
+    }
+  ],
+  "members": [{ "id": "m1", "username": "alice", "fullName": "Alice" }]
+}"##;
+
+    #[test]
+    fn import() {
+        let markdown = Export::from_json(SAMPLE).unwrap().to_markdown();
+
+        assert_eq!(
+            markdown,
+            "\
+# Website relaunch
+
+## To do
+
+- [ ] Write copy
+  - Review
+    - [ ] proofread
+  - Pages
+    - [x] about
+- [ ] Pick a font #design-work #red @alice due:2026-03-01
+      Serif?
+      \\- ask Bob
+  - [x] shortlist
+  - [ ] compare
+
+## Doing
+
+- [x] Set up hosting
+"
+        );
+
+        let (board, _) = Board::parse(&markdown);
+        let (_, font) = &board.items()[5];
+        let metadata = &font.element.metadata;
+        let tags: Vec<_> = metadata.tags.iter().map(|t| &*t.element).collect();
+        assert_eq!(tags, ["design-work", "red"]);
+        assert_eq!(metadata.assignees[0].element, "alice");
+        assert_eq!(
+            font.element
+                .nested_list
+                .as_ref()
+                .unwrap()
+                .element
+                .items
+                .len(),
+            2
+        );
+    }
+}
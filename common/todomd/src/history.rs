@@ -0,0 +1,471 @@
+//! Local history: a copy of the file each time it's saved, like the local history of editors.
+//!
+//! Snapshots of `TODO.md` go in `.history/TODO.md/` next to it, or in another directory given
+//! with [`History::with_dir`]. Each snapshot is named after the time it was taken, which is also
+//! its id. Restoring a snapshot is a save like any other: the current contents are recorded
+//! first, so a restore can itself be undone by restoring the snapshot taken before it.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDateTime, SubsecRound, TimeDelta};
+
+/// The format of snapshot ids, and of their file names
+const ID_FORMAT: &str = "%Y%m%dT%H%M%S%.3f";
+
+/// Marks snapshots taken by a restore, as in `20261018T101530.123.restore-20261017T090000.000.md`
+const RESTORE: &str = ".restore-";
+
+/// How many snapshots are kept. The newest snapshot is always kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retention {
+    /// At most this many snapshots
+    pub max_count: usize,
+    /// No snapshots older than this
+    pub max_age: Option<TimeDelta>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            max_count: 100,
+            max_age: Some(TimeDelta::days(30)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    pub id: String,
+    pub time: NaiveDateTime,
+    /// The snapshot that was restored, if this one was taken by [`History::restore`]
+    pub restored_from: Option<String>,
+}
+
+impl Snapshot {
+    fn file_name(&self) -> String {
+        match &self.restored_from {
+            Some(from) => format!("{}{RESTORE}{from}.md", self.id),
+            None => format!("{}.md", self.id),
+        }
+    }
+
+    fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.strip_suffix(".md")?;
+        let (id, restored_from) = match name.split_once(RESTORE) {
+            Some((id, from)) => (id, Some(from.to_string())),
+            None => (name, None),
+        };
+
+        Some(Snapshot {
+            id: id.to_string(),
+            time: NaiveDateTime::parse_from_str(id, ID_FORMAT).ok()?,
+            restored_from,
+        })
+    }
+}
+
+/// The history of one file
+#[derive(Debug, Clone)]
+pub struct History {
+    file: PathBuf,
+    dir: PathBuf,
+    retention: Retention,
+}
+
+impl History {
+    /// The history of `file`, in the `.history` directory next to it
+    pub fn new(file: impl Into<PathBuf>) -> Self {
+        let file = file.into();
+        let dir = file.parent().unwrap_or(Path::new("")).join(".history");
+
+        History {
+            file,
+            dir,
+            retention: Retention::default(),
+        }
+    }
+
+    /// Keeps the snapshots in `dir` instead of `.history`
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// The file whose versions are kept
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Where the snapshots of the file are: a directory named after the file, so that the files
+    /// of a directory can share a history directory
+    pub fn snapshots_dir(&self) -> PathBuf {
+        let name = self.file.file_name().unwrap_or("file".as_ref());
+        self.dir.join(name)
+    }
+
+    /// The snapshots, oldest first
+    pub fn list(&self) -> io::Result<Vec<Snapshot>> {
+        let entries = match fs::read_dir(self.snapshots_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut snapshots = vec![];
+        for entry in entries {
+            if let Some(snapshot) = entry?
+                .file_name()
+                .to_str()
+                .and_then(Snapshot::from_file_name)
+            {
+                snapshots.push(snapshot);
+            }
+        }
+        snapshots.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(snapshots)
+    }
+
+    fn find(&self, id: &str) -> io::Result<Snapshot> {
+        self.list()?
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no snapshot {id}")))
+    }
+
+    /// The contents of the file when a snapshot was taken
+    pub fn read(&self, id: &str) -> io::Result<String> {
+        let snapshot = self.find(id)?;
+        fs::read_to_string(self.snapshots_dir().join(snapshot.file_name()))
+    }
+
+    /// Takes a snapshot of `contents`, unless the newest snapshot has the same contents. Returns
+    /// the new snapshot, if one was taken.
+    pub fn record(&self, contents: &str) -> io::Result<Option<Snapshot>> {
+        self.record_at(contents, None, Local::now().naive_local())
+    }
+
+    fn record_at(
+        &self,
+        contents: &str,
+        restored_from: Option<&str>,
+        now: NaiveDateTime,
+    ) -> io::Result<Option<Snapshot>> {
+        let snapshots = self.list()?;
+
+        if restored_from.is_none()
+            && let Some(newest) = snapshots.last()
+            && self.read(&newest.id).is_ok_and(|newest| newest == contents)
+        {
+            return Ok(None);
+        }
+
+        // Ids must be unique and increasing, even for saves within the same millisecond or when
+        // the clock goes back
+        let mut time = now.trunc_subsecs(3);
+        if let Some(newest) = snapshots.last()
+            && newest.time >= time
+        {
+            time = newest.time + TimeDelta::milliseconds(1);
+        }
+
+        let snapshot = Snapshot {
+            id: time.format(ID_FORMAT).to_string(),
+            time,
+            restored_from: restored_from.map(String::from),
+        };
+
+        fs::create_dir_all(self.snapshots_dir())?;
+        fs::write(self.snapshots_dir().join(snapshot.file_name()), contents)?;
+        self.prune(now)?;
+
+        Ok(Some(snapshot))
+    }
+
+    /// Removes the snapshots the retention policy doesn't keep
+    fn prune(&self, now: NaiveDateTime) -> io::Result<()> {
+        let snapshots = self.list()?;
+        let Some((_, older)) = snapshots.split_last() else {
+            return Ok(());
+        };
+
+        let excess = snapshots.len().saturating_sub(self.retention.max_count);
+
+        for (i, snapshot) in older.iter().enumerate() {
+            let too_old = self
+                .retention
+                .max_age
+                .is_some_and(|max_age| now - snapshot.time > max_age);
+
+            if i < excess || too_old {
+                fs::remove_file(self.snapshots_dir().join(snapshot.file_name()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes `contents` to the file, with snapshots of the file before and after. The file is
+    /// replaced at once, see [`write_atomically`].
+    pub fn save(&self, contents: &str) -> io::Result<()> {
+        self.save_at(contents, None, Local::now().naive_local())
+    }
+
+    fn save_at(
+        &self,
+        contents: &str,
+        restored_from: Option<&str>,
+        now: NaiveDateTime,
+    ) -> io::Result<()> {
+        // The file as it was, in case it was changed by something that doesn't keep history
+        match fs::read_to_string(&self.file) {
+            Ok(previous) => {
+                self.record_at(&previous, None, now)?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        write_atomically(&self.file, contents)?;
+        self.record_at(contents, restored_from, now)?;
+
+        Ok(())
+    }
+
+    /// Puts the file back as it was in a snapshot, and returns the contents. The restore is
+    /// recorded, and the file as it was before can be restored from the snapshot before it.
+    pub fn restore(&self, id: &str) -> io::Result<String> {
+        let contents = self.read(id)?;
+        self.save_at(&contents, Some(id), Local::now().naive_local())?;
+        Ok(contents)
+    }
+}
+
+/// Writes a file without ever leaving it half written: `contents` go to a temporary file next to
+/// it, which then replaces it. The file keeps its permissions, and if it's a symbolic link, the
+/// file it points to is replaced instead of the link.
+///
+/// The bytes are written as they are. Edits keep the line endings, byte order mark and final
+/// newline of the file, see [`Board::edit`](crate::Board::edit).
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let path = match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
+        Err(e) => return Err(e),
+    };
+    let permissions = match fs::metadata(&path) {
+        Ok(metadata) => Some(metadata.permissions()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    let name = path
+        .file_name()
+        .unwrap_or("file".as_ref())
+        .to_string_lossy();
+    let temp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+
+    let write = || {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        if let Some(permissions) = permissions {
+            fs::set_permissions(&temp, permissions)?;
+        }
+        fs::rename(&temp, &path)
+    };
+
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A history in a new temporary directory, which is removed when dropped
+    struct TempHistory {
+        dir: PathBuf,
+        history: History,
+    }
+
+    impl TempHistory {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("todomd-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+
+            TempHistory {
+                history: History::new(dir.join("TODO.md")),
+                dir,
+            }
+        }
+    }
+
+    impl Drop for TempHistory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn contents(history: &History) -> Vec<String> {
+        history
+            .list()
+            .unwrap()
+            .iter()
+            .map(|s| history.read(&s.id).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn saves_keep_both_versions() {
+        let temp = TempHistory::new("saves");
+        let history = &temp.history;
+        fs::write(temp.dir.join("TODO.md"), "- [ ] a\n").unwrap();
+
+        let now = time("2026-10-18 10:00:00");
+        history.save_at("- [x] a\n", None, now).unwrap();
+        history.save_at("- [x] a\n- [ ] b\n", None, now).unwrap();
+
+        assert_eq!(
+            contents(history),
+            ["- [ ] a\n", "- [x] a\n", "- [x] a\n- [ ] b\n"]
+        );
+        let ids: Vec<_> = history.list().unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(
+            ids,
+            [
+                "20261018T100000.000",
+                "20261018T100000.001",
+                "20261018T100000.002"
+            ]
+        );
+        assert!(temp.dir.join(".history/TODO.md").is_dir());
+    }
+
+    #[test]
+    fn restores_can_be_undone() {
+        let temp = TempHistory::new("restores");
+        let history = &temp.history;
+        let file = temp.dir.join("TODO.md");
+
+        history.save("first\n").unwrap();
+        history.save("second\n").unwrap();
+        let snapshots = history.list().unwrap();
+        assert_eq!(snapshots.len(), 2);
+
+        assert_eq!(history.restore(&snapshots[0].id).unwrap(), "first\n");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "first\n");
+
+        let after = history.list().unwrap();
+        assert_eq!(after.len(), 3);
+        assert_eq!(
+            after[2].restored_from.as_deref(),
+            Some(snapshots[0].id.as_str())
+        );
+
+        // Undo the restore
+        history.restore(&snapshots[1].id).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "second\n");
+    }
+
+    #[test]
+    fn retention() {
+        let temp = TempHistory::new("retention");
+        let history = temp.history.clone().with_retention(Retention {
+            max_count: 3,
+            max_age: Some(TimeDelta::days(7)),
+        });
+
+        history
+            .record_at("old", None, time("2026-01-01 00:00:00"))
+            .unwrap();
+        for (i, day) in ["10", "11", "12", "13"].iter().enumerate() {
+            history
+                .record_at(
+                    &i.to_string(),
+                    None,
+                    time(&format!("2026-01-{day} 00:00:00")),
+                )
+                .unwrap();
+        }
+        assert_eq!(contents(&history), ["1", "2", "3"]);
+
+        // The newest snapshot is kept however old it is
+        history
+            .record_at("3", None, time("2027-01-01 00:00:00"))
+            .unwrap();
+        history.prune(time("2027-01-01 00:00:00")).unwrap();
+        assert_eq!(contents(&history), ["3"]);
+    }
+
+    #[test]
+    fn saves_keep_the_bytes_and_permissions_of_the_file() {
+        let temp = TempHistory::new("bytes");
+        let file = temp.dir.join("TODO.md");
+        // A byte order mark, CRLF line endings, and no final newline
+        let source = "\u{feff}## Todo\r\n\r\n- [ ] first\r\n- [ ] second";
+        fs::write(&file, source).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&file, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        let (board, _) = crate::Board::parse(source);
+        let operation = crate::Operation::Insert {
+            list: crate::ListPath::new(0, 0),
+            index: 1,
+            text: "inserted".into(),
+            checkbox: Some(false),
+        };
+        let (edited, _) = board.apply(source, &operation).unwrap();
+        temp.history.save(&edited).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "\u{feff}## Todo\r\n\r\n- [ ] first\r\n- [ ] inserted\r\n- [ ] second"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        let mut names: Vec<_> = fs::read_dir(&temp.dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, [".history", "TODO.md"], "no temporary file is left");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saves_through_symbolic_links() {
+        let temp = TempHistory::new("symlink");
+        let target = temp.dir.join("target.md");
+        let link = temp.dir.join("TODO.md");
+        fs::write(&target, "- [ ] a\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_atomically(&link, "- [x] a\n").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "- [x] a\n");
+    }
+}
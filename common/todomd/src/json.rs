@@ -0,0 +1,220 @@
+//! The JSON form of a board, for the GUI and for other tools. Needs the `serde` feature.
+//!
+//! A board is written as a versioned [`Document`], described by the JSON schema in [`SCHEMA`]
+//! (`schema/board.schema.json`). Ranges are byte offsets into the markdown source. With
+//! [`JsonOptions::text`], each range also carries the text it covers, so consumers don't need the
+//! source to show the board.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Board;
+
+/// The version of the JSON document. It changes whenever the shape of the document changes in a
+/// way that older consumers can't read.
+pub const VERSION: u32 = 1;
+
+/// The JSON schema of [`Document`]
+pub const SCHEMA: &str = include_str!("../schema/board.schema.json");
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Document {
+    pub version: u32,
+    pub board: Board,
+}
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    board: &'a Board,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    /// Adds the source text of each range next to its `start` and `end`
+    pub text: bool,
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    /// The document was written for another version of the format
+    Version(Option<u64>),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Version(Some(version)) => {
+                write!(
+                    f,
+                    "unsupported document version {version}, expected {VERSION}"
+                )
+            }
+            JsonError::Version(None) => write!(f, "document has no version"),
+            JsonError::Json(e) => write!(f, "invalid document: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        JsonError::Json(e)
+    }
+}
+
+/// Adds a `text` to each range object in `value`
+fn add_text(value: &mut Value, source: &str) {
+    match value {
+        Value::Object(object) => {
+            if let (Some(start), Some(end), 2) = (
+                object.get("start").and_then(Value::as_u64),
+                object.get("end").and_then(Value::as_u64),
+                object.len(),
+            ) && let Some(text) = source.get(start as usize..end as usize)
+            {
+                object.insert("text".to_string(), Value::String(text.to_string()));
+                return;
+            }
+
+            for value in object.values_mut() {
+                add_text(value, source);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                add_text(value, source);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Removes the `text` of range objects, which `serde` doesn't accept in ranges
+fn remove_text(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if object.len() == 3
+                && object.get("start").is_some_and(Value::is_u64)
+                && object.get("end").is_some_and(Value::is_u64)
+                && object.remove("text").is_some()
+            {
+                return;
+            }
+
+            for value in object.values_mut() {
+                remove_text(value);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                remove_text(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Board {
+    /// Writes the board as a JSON [`Document`]. `source` must be the text the board was parsed
+    /// from.
+    pub fn to_json(&self, source: &str, options: &JsonOptions) -> Value {
+        let document = DocumentRef {
+            version: VERSION,
+            board: self,
+        };
+        let mut value = serde_json::to_value(document).expect("boards serialize to JSON");
+
+        if options.text {
+            add_text(&mut value, source);
+        }
+
+        value
+    }
+
+    /// Reads a board from a JSON [`Document`]. The `text` of ranges is ignored, and the parse
+    /// options are the default ones.
+    pub fn from_json(json: &str) -> Result<Board, JsonError> {
+        Self::from_json_value(serde_json::from_str(json)?)
+    }
+
+    /// Reads a board from a JSON [`Document`] that was already parsed, like [`Board::from_json`]
+    pub fn from_json_value(mut value: Value) -> Result<Board, JsonError> {
+        match value.get("version").and_then(Value::as_u64) {
+            Some(version) if version == VERSION as u64 => {}
+            version => return Err(JsonError::Version(version)),
+        }
+
+        remove_text(&mut value);
+        let document: Document = serde_json::from_value(value)?;
+        Ok(document.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ItemPath;
+
+    const SOURCE: &str = "## Todo\n\n- [ ] first @alice due:2026-11-01\n  1. nested\n";
+
+    #[test]
+    fn round_trip() {
+        let (board, _) = Board::parse(SOURCE);
+        let json = board.to_json(SOURCE, &JsonOptions::default()).to_string();
+        let read = Board::from_json(&json).unwrap();
+
+        let paths = |board: &Board| {
+            board
+                .items()
+                .into_iter()
+                .map(|(path, item)| (path, item.range.clone(), item.element.metadata.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(&read), paths(&board));
+        assert_eq!(
+            read.item(&ItemPath::new(0, 0, 0)).unwrap().text(SOURCE),
+            "first @alice due:2026-11-01"
+        );
+    }
+
+    #[test]
+    fn ranges_can_carry_their_text() {
+        let (board, _) = Board::parse(SOURCE);
+        let value = board.to_json(SOURCE, &JsonOptions { text: true });
+
+        let section = &value["board"]["sections"][0];
+        assert_eq!(section["range"]["text"], SOURCE);
+        let item = &section["element"]["lists"][0]["element"]["items"][0]["element"];
+        assert_eq!(item["checkbox"]["range"]["text"], "[ ]");
+        assert_eq!(item["metadata"]["due"]["element"], "2026-11-01");
+        assert_eq!(item["metadata"]["due"]["range"]["text"], "due:2026-11-01");
+        let nested = &item["nested_list"]["element"];
+        assert_eq!(nested["kind"]["ordered"]["delimiter"], ".");
+
+        // Text is ignored when reading
+        assert!(Board::from_json(&value.to_string()).is_ok());
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let json = r#"{"version": 2, "board": {"sections": []}}"#;
+        assert!(matches!(
+            Board::from_json(json),
+            Err(JsonError::Version(Some(2)))
+        ));
+    }
+
+    #[test]
+    fn schema_is_valid_json() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        assert_eq!(
+            schema["properties"]["version"]["const"],
+            Value::from(VERSION)
+        );
+    }
+}
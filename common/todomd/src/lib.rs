@@ -1,30 +1,52 @@
 #![allow(unused)]
 
-use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::sync::Arc;
 
-mod rangeset;
+use log::trace;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
 
-use rangeset::{Range, RangeSet};
+mod diagnostic;
+mod edit;
+pub mod formats;
+pub mod history;
+#[cfg(feature = "serde")]
+pub mod json;
+mod metadata;
+mod path;
+mod query;
+mod rangeset;
+mod rebase;
+pub mod render;
+mod undo;
 
-macro_rules! print_helper {
-    ($ident: expr, $text: expr) => {
-        println!("{:ident$}{:?}", "", $text, ident = $ident);
-    };
-    ("-", $ident: expr, $text: expr) => {
-        println!("{:ident$} - {:?}", "", $text, ident = $ident);
-    };
-}
+pub use diagnostic::{Diagnostic, Severity};
+pub use edit::{Edit, EditError, Operation, Splice};
+pub use metadata::{
+    Field, Grammar, Inline, Metadata, ObsidianTasks, ParsePriorityError, Priority, TodoTxt, Word,
+};
+pub use path::{ItemId, ItemPath, ItemRef, ListPath, ParsePathError};
+pub use query::{Match, Query, QueryError};
+pub use rangeset::{Range, RangeSet};
+pub use rebase::{Conflict, ConflictReason, OperationLog, Rebased};
+pub use render::{Outline, Renderer};
+pub use undo::UndoStack;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span<I> {
     pub element: I,
     pub range: Range,
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     pub checkbox: Option<Span<bool>>,
     pub contents: RangeSet,
+    /// The ranges of plain text among the contents, without markup, code spans or link
+    /// destinations
+    pub plain_text: RangeSet,
+    pub metadata: Metadata,
     pub nested_list: Option<Span<List>>,
 }
 
@@ -38,27 +60,80 @@ impl Item {
 }
 
 impl Span<Item> {
-    pub fn print(&self, source: &str, ident: usize, verbose: bool) {
-        print_helper!(
-            "-",
-            ident,
-            self.element.checkbox.as_ref().map(|x| x.element)
-        );
-        for range in &self.element.contents {
-            print_helper!(ident + 2, &source[range.clone()]);
-        }
-        if let Some(nested) = &self.element.nested_list {
-            nested.print(source, ident + 2, verbose);
+    /// The range of the item text, from its first to its last content range, without trailing
+    /// whitespace. `None` if the item has no text.
+    pub fn text_range(&self, source: &str) -> Option<Range> {
+        let first = self.element.contents.iter().next()?;
+        let last = self.element.contents.iter().last()?;
+        let end = first.start + source[first.start..last.end].trim_end().len();
+
+        Some(first.start..end)
+    }
+
+    /// The raw markdown of the item text, without its marker, checkbox and nested list
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        self.text_range(source).map_or("", |range| &source[range])
+    }
+
+    /// The item text without the tokens of its metadata fields, on a single line
+    pub fn text_without_fields(&self, source: &str) -> String {
+        let Some(range) = self.text_range(source) else {
+            return String::new();
+        };
+
+        let mut words = vec![];
+        let mut start = range.start;
+        for field in self.element.metadata.fields() {
+            words.extend(source[start..field.range.start].split_whitespace());
+            start = field.range.end;
         }
-        if verbose {
-            println!("(item source: {:?})", &source[self.range.clone()]);
-            println!();
+        words.extend(source[start..range.end].split_whitespace());
+
+        words.join(" ")
+    }
+}
+
+/// The kind of a list, and the marker its items use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ListKind {
+    /// A bullet list, with `-`, `*` or `+`
+    Bullet(char),
+    /// An ordered list, like `1.` or `3)`. `start` is the number of the first item.
+    Ordered { start: u64, delimiter: char },
+}
+
+impl Default for ListKind {
+    fn default() -> Self {
+        ListKind::Bullet('-')
+    }
+}
+
+impl ListKind {
+    /// Reads the kind of a list from the marker of its first item, at `source[pos..]`
+    fn parse(source: &str, start: Option<u64>, pos: usize) -> Self {
+        let rest = source[pos..].trim_start_matches([' ', '\t']);
+
+        match start {
+            None => ListKind::Bullet(rest.chars().next().unwrap_or('-')),
+            Some(start) => {
+                let digits = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                ListKind::Ordered {
+                    start,
+                    delimiter: rest[digits..].chars().next().unwrap_or('.'),
+                }
+            }
         }
     }
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct List {
+    pub kind: ListKind,
     pub items: Vec<Span<Item>>,
 }
 
@@ -71,79 +146,278 @@ impl List {
     }
 }
 
-impl Span<List> {
-    pub fn print(&self, source: &str, ident: usize, verbose: bool) {
-        for item in &self.element.items {
-            item.print(source, ident, verbose);
+/// A heading that starts a [`Section`]. `title` holds the ranges of the heading text, without
+/// the `#` markers.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Heading {
+    pub level: u8,
+    pub title: RangeSet,
+}
+
+impl Heading {
+    pub fn span(self, range: Range) -> Span<Self> {
+        Span {
+            element: self,
+            range,
         }
+    }
+}
 
-        if verbose {
-            println!("(list source: {:?})", &source[self.range.clone()]);
-            println!();
+impl Span<Heading> {
+    /// The raw markdown of the heading text, from the first to the last title range
+    pub fn title<'a>(&self, source: &'a str) -> &'a str {
+        match (
+            self.element.title.iter().next(),
+            self.element.title.iter().last(),
+        ) {
+            (Some(first), Some(last)) => &source[first.start..last.end],
+            _ => "",
         }
     }
 }
 
+/// A named column of the board: a heading, and the lists and free prose that come after it,
+/// until the next heading of the same level or above.
+///
+/// Content before the first heading goes into a section without a heading.
 #[derive(Debug, Default)]
-pub struct Board {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Section {
+    pub heading: Option<Span<Heading>>,
     pub lists: Vec<Span<List>>,
+    pub prose: RangeSet,
 }
 
-impl Board {
-    pub fn print(&self, source: &str, verbose: bool) {
-        for list in &self.lists {
-            list.print(source, 0, verbose);
-            println!();
-            println!();
+impl Section {
+    pub fn span(self, range: Range) -> Span<Self> {
+        Span {
+            element: self,
+            range,
         }
     }
 
-    pub fn parse(input: &str) -> Self {
-        let mut board = Board::default();
+    fn is_empty(&self) -> bool {
+        self.heading.is_none() && self.lists.is_empty() && self.prose.is_empty()
+    }
+}
+
+impl Span<Section> {
+    pub fn title<'a>(&self, source: &'a str) -> Option<&'a str> {
+        self.element.heading.as_ref().map(|h| h.title(source))
+    }
+}
+
+/// How to split a document into sections, and how to read the metadata of items.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Headings at this level or above start a new section, deeper headings are kept as prose of
+    /// the current section. With `None`, the level is detected from the headings that come right
+    /// before lists: `##` in the todomd/todo.md layout, `###` in the todo-md/todo-md one.
+    pub section_level: Option<u8>,
+    /// The grammars metadata is read with, in order of precedence. New fields are written with
+    /// the first grammar that has a syntax for them. Only [`Inline`] by default.
+    pub grammars: Vec<Arc<dyn Grammar>>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            section_level: None,
+            grammars: vec![Arc::new(Inline)],
+        }
+    }
+}
+
+/// The section level used when the document has no heading followed by a list
+pub const DEFAULT_SECTION_LEVEL: u8 = 2;
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    level as u8
+}
+
+/// The byte order mark that some editors put at the start of UTF-8 files.
+const BOM: &str = "\u{feff}";
+
+/// The markdown of `input`, after its byte order mark if it has one, and where it starts. The
+/// markdown parser would take the mark for text, and miss a heading or a list on the first line.
+fn markdown(input: &str) -> (&str, usize) {
+    match input.strip_prefix(BOM) {
+        Some(markdown) => (markdown, BOM.len()),
+        None => (input, 0),
+    }
+}
 
-        let mut list_stack: Vec<List> = vec![];
+/// Finds the heading level that most often introduces a top-level list. Ties go to the
+/// shallowest level.
+fn detect_section_level(input: &str) -> u8 {
+    let mut counts = [0usize; 7];
+    let mut last_heading = None;
+    let mut list_depth = 0;
+
+    for event in Parser::new_ext(markdown(input).0, Options::ENABLE_TASKLISTS) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) if list_depth == 0 => {
+                last_heading = Some(heading_level(level));
+            }
+            Event::Start(Tag::List(_)) => {
+                if list_depth == 0
+                    && let Some(level) = last_heading.take()
+                {
+                    counts[level as usize] += 1;
+                }
+                list_depth += 1;
+            }
+            Event::End(TagEnd::List(_)) => list_depth -= 1,
+            _ => {}
+        }
+    }
+
+    (1..=6)
+        .rev()
+        .max_by_key(|&level| counts[level])
+        .filter(|&level| counts[level] > 0)
+        .map(|level| level as u8)
+        .unwrap_or(DEFAULT_SECTION_LEVEL)
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Board {
+    pub sections: Vec<Span<Section>>,
+    /// The options the board was parsed with, with the section level resolved
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub options: ParseOptions,
+}
+
+impl Board {
+    /// All top-level lists, across sections
+    pub fn lists(&self) -> impl Iterator<Item = &Span<List>> {
+        self.sections.iter().flat_map(|s| &s.element.lists)
+    }
+
+    /// The first section whose heading text is `title`
+    pub fn section(&self, source: &str, title: &str) -> Option<&Span<Section>> {
+        self.sections
+            .iter()
+            .find(|s| s.title(source).is_some_and(|t| t.trim() == title.trim()))
+    }
+
+    pub fn parse(input: &str) -> (Self, Vec<Diagnostic>) {
+        Self::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses a board. Markdown is never invalid, but some structures don't map well to a board,
+    /// like an item with two nested lists; they are reported as diagnostics.
+    pub fn parse_with(input: &str, options: &ParseOptions) -> (Self, Vec<Diagnostic>) {
+        let section_level = options
+            .section_level
+            .unwrap_or_else(|| detect_section_level(input));
+
+        let mut board = Board {
+            sections: vec![],
+            options: ParseOptions {
+                section_level: Some(section_level),
+                grammars: options.grammars.clone(),
+            },
+        };
+
+        let mut diagnostics = vec![];
+
+        let (markdown, offset) = markdown(input);
+
+        let mut section = Section::default();
+        let mut section_start = offset;
+
+        let mut in_heading = false;
+
+        // Lists being parsed, and whether a loose list warning was already given for them
+        let mut list_stack: Vec<(List, bool)> = vec![];
 
         let mut item_stack: Vec<Item> = vec![];
 
-        let parser = Parser::new_ext(input, Options::ENABLE_TASKLISTS).into_offset_iter();
+        let parser = Parser::new_ext(markdown, Options::ENABLE_TASKLISTS)
+            .into_offset_iter()
+            .map(|(event, range)| (event, range.start + offset..range.end + offset));
 
         for (event, range) in parser {
+            trace!("{event:?} at {range:?}: {:?}", &input[range.clone()]);
+
             match event {
-                Event::Start(Tag::List(None)) => {
-                    println!("Found unordered list start\n");
+                Event::Start(Tag::Heading { level, .. })
+                    if list_stack.is_empty() && heading_level(level) <= section_level =>
+                {
+                    let previous = std::mem::take(&mut section);
+                    if !previous.is_empty() {
+                        board
+                            .sections
+                            .push(previous.span(section_start..range.start));
+                    }
 
-                    list_stack.push(List::default());
+                    section_start = range.start;
+                    section.heading = Some(
+                        Heading {
+                            level: heading_level(level),
+                            title: RangeSet::default(),
+                        }
+                        .span(range.clone()),
+                    );
+                    in_heading = true;
                 }
-                Event::End(TagEnd::List(false)) => {
-                    println!("Found unordered list end\n");
-
-                    let current_list = list_stack.pop().unwrap();
+                Event::End(TagEnd::Heading(_)) if in_heading => {
+                    in_heading = false;
+                }
+                Event::Start(Tag::List(start)) => {
+                    let list = List {
+                        kind: ListKind::parse(input, start, range.start),
+                        items: vec![],
+                    };
+                    list_stack.push((list, false));
+                }
+                Event::End(TagEnd::List(_)) => {
+                    let Some((current_list, _)) = list_stack.pop() else {
+                        diagnostics.push(Diagnostic::error(range, "list end without a start"));
+                        continue;
+                    };
 
                     if let Some(current_item) = item_stack.last_mut() {
                         if current_item.nested_list.is_some() {
-                            panic!("a list item should not have two sub lists");
+                            diagnostics.push(Diagnostic::warning(
+                                range,
+                                "item has more than one nested list, only the first one is used",
+                            ));
+                        } else {
+                            current_item.nested_list = Some(current_list.span(range.clone()));
                         }
-                        current_item.nested_list = Some(current_list.span(range.clone()));
                     } else {
-                        board.lists.push(current_list.span(range.clone()));
+                        section.lists.push(current_list.span(range.clone()));
                     }
                 }
                 Event::Start(Tag::Item) => {
-                    println!("Found item start\n");
-
                     item_stack.push(Item::default());
                 }
                 Event::End(TagEnd::Item) => {
-                    println!("Found item end\n");
-
-                    let current_list = list_stack.last_mut().unwrap();
-                    let current_item = item_stack.pop().unwrap();
+                    let Some(mut current_item) = item_stack.pop() else {
+                        diagnostics.push(Diagnostic::error(range, "item end without a start"));
+                        continue;
+                    };
+                    current_item.metadata = metadata::scan(
+                        input,
+                        current_item.plain_text.iter().cloned(),
+                        &options.grammars,
+                        &mut diagnostics,
+                    );
+                    let Some((current_list, _)) = list_stack.last_mut() else {
+                        diagnostics.push(Diagnostic::error(range, "item outside of a list"));
+                        continue;
+                    };
                     current_list.items.push(current_item.span(range.clone()));
                 }
                 Event::TaskListMarker(marked) => {
-                    println!("Found task list marker: {marked}\n");
-
-                    let current_item = item_stack.last_mut().unwrap();
+                    let Some(current_item) = item_stack.last_mut() else {
+                        diagnostics.push(Diagnostic::error(range, "checkbox outside of an item"));
+                        continue;
+                    };
                     current_item.checkbox = Some(Span {
                         element: marked,
                         range: range.clone(),
@@ -151,15 +425,243 @@ impl Board {
                 }
                 _ => {
                     if let Some(current_item) = item_stack.last_mut() {
-                        println!("Found something else inside item\n");
+                        // Items of tight lists have no paragraphs
+                        if let Event::Start(Tag::Paragraph) = event
+                            && let Some((_, warned @ false)) = list_stack.last_mut()
+                        {
+                            *warned = true;
+                            diagnostics.push(Diagnostic::warning(
+                                range.clone(),
+                                "loose list: blank lines between items may be lost in edits",
+                            ));
+                        }
+                        if let Event::Text(_) = event
+                            && !range.is_empty()
+                        {
+                            current_item.plain_text.insert_range(range.clone());
+                        }
                         current_item.contents.insert_range(range.clone());
+                    } else if in_heading && !range.is_empty() {
+                        if let Some(heading) = &mut section.heading {
+                            heading.element.title.insert_range(range.clone());
+                        }
+                    } else if list_stack.is_empty() && !range.is_empty() {
+                        section.prose.insert_range(range.clone());
                     }
                 }
             }
+        }
+
+        if !section.is_empty() {
+            board
+                .sections
+                .push(section.span(section_start..input.len()));
+        }
+
+        (board, diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TODOMD_LAYOUT: &str = "\
+# Project
+
+Some description.
+
+## Todo
+
+- [ ] first
+- [ ] second
+  - [x] nested
+
+## Done
+
+- [x] third
+";
+
+    const TODO_MD_LAYOUT: &str = "\
+# Project
+
+### Ongoing
+- [ ] first
+
+#### Notes
+
+Not a column.
 
-            println!("{:?}\n", &input[range.clone()]);
+### Done
+- [x] second
+- [x] third
+";
+
+    fn titles<'a>(board: &Board, source: &'a str) -> Vec<Option<&'a str>> {
+        board.sections.iter().map(|s| s.title(source)).collect()
+    }
+
+    #[test]
+    fn detects_level_two_sections() {
+        let (board, _) = Board::parse(TODOMD_LAYOUT);
+
+        assert_eq!(
+            titles(&board, TODOMD_LAYOUT),
+            vec![Some("Project"), Some("Todo"), Some("Done")]
+        );
+        let todo = &board.sections[1];
+        assert_eq!(todo.element.heading.as_ref().unwrap().element.level, 2);
+        assert_eq!(todo.element.lists.len(), 1);
+        assert_eq!(todo.element.lists[0].element.items.len(), 2);
+        assert_eq!(board.sections[2].element.lists[0].element.items.len(), 1);
+
+        let project = &board.sections[0];
+        assert!(project.element.lists.is_empty());
+        let prose: Vec<_> = project
+            .element
+            .prose
+            .iter()
+            .map(|r| &TODOMD_LAYOUT[r.clone()])
+            .collect();
+        assert_eq!(prose, vec!["Some description.\n"]);
+    }
+
+    #[test]
+    fn detects_level_three_sections() {
+        let (board, _) = Board::parse(TODO_MD_LAYOUT);
+
+        assert_eq!(
+            titles(&board, TODO_MD_LAYOUT),
+            vec![Some("Project"), Some("Ongoing"), Some("Done")]
+        );
+        let ongoing = board.section(TODO_MD_LAYOUT, "Ongoing").unwrap();
+        assert_eq!(ongoing.element.lists.len(), 1);
+        assert!(TODO_MD_LAYOUT[ongoing.range.clone()].contains("Not a column."));
+        assert_eq!(board.lists().count(), 2);
+    }
+
+    #[test]
+    fn section_ranges_cover_the_document() {
+        let (board, _) = Board::parse(TODO_MD_LAYOUT);
+
+        assert_eq!(board.sections.first().unwrap().range.start, 0);
+        assert_eq!(
+            board.sections.last().unwrap().range.end,
+            TODO_MD_LAYOUT.len()
+        );
+        for pair in board.sections.windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start);
         }
+    }
+
+    #[test]
+    fn explicit_section_level() {
+        let options = ParseOptions {
+            section_level: Some(1),
+            ..ParseOptions::default()
+        };
+        let (board, _) = Board::parse_with(TODO_MD_LAYOUT, &options);
+
+        assert_eq!(titles(&board, TODO_MD_LAYOUT), vec![Some("Project")]);
+        assert_eq!(board.sections[0].element.lists.len(), 2);
+    }
+
+    #[test]
+    fn odd_structures_give_warnings() {
+        let source = "- item\n  - first\n  * second\n- loose\n\n- list\n";
+        let (board, diagnostics) = Board::parse(source);
+
+        let item = &board.sections[0].element.lists[0].element.items[0];
+        assert_eq!(
+            item.element
+                .nested_list
+                .as_ref()
+                .unwrap()
+                .element
+                .items
+                .len(),
+            1
+        );
+
+        let messages: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line_col(source)))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![(Severity::Warning, (1, 3)), (Severity::Warning, (3, 3))]
+        );
+    }
+
+    #[test]
+    fn ordered_and_mixed_lists() {
+        let source = "3) three\n4) four\n   - [ ] bullet\n     1. one\n\n+ plus\n";
+        let (board, diagnostics) = Board::parse(source);
+        assert!(diagnostics.is_empty());
+
+        let lists = &board.sections[0].element.lists;
+        assert_eq!(lists.len(), 2);
+        assert_eq!(
+            lists[0].element.kind,
+            ListKind::Ordered {
+                start: 3,
+                delimiter: ')'
+            }
+        );
+        assert_eq!(lists[1].element.kind, ListKind::Bullet('+'));
+
+        let bullet = &lists[0].element.items[1]
+            .element
+            .nested_list
+            .as_ref()
+            .unwrap();
+        assert_eq!(bullet.element.kind, ListKind::Bullet('-'));
+        let ordered = bullet.element.items[0]
+            .element
+            .nested_list
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            ordered.element.kind,
+            ListKind::Ordered {
+                start: 1,
+                delimiter: '.'
+            }
+        );
+        assert_eq!(ordered.element.items[0].text(source), "one");
+    }
+
+    #[test]
+    fn content_before_the_first_heading() {
+        let source = "- [ ] loose\n\n## Column\n\n- [ ] task\n";
+        let (board, _) = Board::parse(source);
+
+        assert_eq!(titles(&board, source), vec![None, Some("Column")]);
+        assert_eq!(board.sections[0].element.lists.len(), 1);
+    }
+
+    #[test]
+    fn crlf_and_byte_order_mark() {
+        let items = |source: &str| -> Vec<(String, String)> {
+            let (board, diagnostics) = Board::parse(source);
+            assert_eq!(diagnostics, []);
+            assert_eq!(
+                titles(&board, source),
+                vec![Some("Project"), Some("Todo"), Some("Done")]
+            );
+
+            board
+                .items()
+                .into_iter()
+                .map(|(_, item)| {
+                    let lines = source[item.range.clone()].replace("\r\n", "\n");
+                    (lines, item.text(source).to_string())
+                })
+                .collect()
+        };
 
-        board
+        let expected = items(TODOMD_LAYOUT);
+        assert_eq!(items(&TODOMD_LAYOUT.replace('\n', "\r\n")), expected);
+        assert_eq!(items(&format!("{BOM}{TODOMD_LAYOUT}")), expected);
     }
 }
@@ -0,0 +1,627 @@
+//! Task metadata written inline in the text of items, like `@alice #backend due:2026-11-01 !high`.
+//!
+//! Fields are read from the plain text of an item, never from code spans or link destinations, by
+//! the [`Grammar`]s listed in [`ParseOptions::grammars`](crate::ParseOptions). Each field keeps
+//! the range of the token it was read from, so editing a field rewrites only that token.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use crate::{Diagnostic, Range, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Priority {
+    Lowest,
+    Low,
+    Medium,
+    High,
+    Highest,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 5] = [
+        Priority::Lowest,
+        Priority::Low,
+        Priority::Medium,
+        Priority::High,
+        Priority::Highest,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Lowest => "lowest",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Highest => "highest",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePriorityError(String);
+
+impl fmt::Display for ParsePriorityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid priority {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePriorityError {}
+
+impl FromStr for Priority {
+    type Err = ParsePriorityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Priority::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParsePriorityError(s.to_string()))
+    }
+}
+
+/// A single piece of metadata, as read by a [`Grammar`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Field {
+    Tag(String),
+    Assignee(String),
+    /// A todo.txt `+project`
+    Project(String),
+    /// A todo.txt `@context`
+    Context(String),
+    Due(NaiveDate),
+    Created(NaiveDate),
+    Completed(NaiveDate),
+    Priority(Priority),
+    /// Any other `key:value` pair
+    Other {
+        key: String,
+        value: String,
+    },
+}
+
+impl Field {
+    /// Whether two fields would take the same slot of [`Metadata`]. Tags, assignees, projects and
+    /// contexts are all of the same kind regardless of their value; other fields have a single
+    /// value per item.
+    pub fn same_kind(&self, other: &Field) -> bool {
+        match (self, other) {
+            (Field::Other { key: a, .. }, Field::Other { key: b, .. }) => a == b,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+/// The metadata of an item. The range of each field covers its whole token, like `due:2026-11-01`
+/// or `📅 2026-11-01`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    pub tags: Vec<Span<String>>,
+    pub assignees: Vec<Span<String>>,
+    pub projects: Vec<Span<String>>,
+    pub contexts: Vec<Span<String>>,
+    pub due: Option<Span<NaiveDate>>,
+    pub created: Option<Span<NaiveDate>>,
+    pub completed: Option<Span<NaiveDate>>,
+    pub priority: Option<Span<Priority>>,
+    /// Other `key:value` pairs
+    pub other: Vec<Span<(String, String)>>,
+}
+
+fn set_once<T>(slot: &mut Option<Span<T>>, element: T, range: Range) -> bool {
+    if slot.is_some() {
+        return false;
+    }
+    *slot = Some(Span { element, range });
+    true
+}
+
+impl Metadata {
+    /// Adds a field. Returns `false` if the field has a single value and it was already set, in
+    /// which case the first one is kept.
+    fn push(&mut self, field: Field, range: Range) -> bool {
+        match field {
+            Field::Tag(tag) => self.tags.push(Span {
+                element: tag,
+                range,
+            }),
+            Field::Assignee(name) => self.assignees.push(Span {
+                element: name,
+                range,
+            }),
+            Field::Project(name) => self.projects.push(Span {
+                element: name,
+                range,
+            }),
+            Field::Context(name) => self.contexts.push(Span {
+                element: name,
+                range,
+            }),
+            Field::Due(date) => return set_once(&mut self.due, date, range),
+            Field::Created(date) => return set_once(&mut self.created, date, range),
+            Field::Completed(date) => return set_once(&mut self.completed, date, range),
+            Field::Priority(priority) => return set_once(&mut self.priority, priority, range),
+            Field::Other { key, value } => {
+                if self.other.iter().any(|f| f.element.0 == key) {
+                    return false;
+                }
+                self.other.push(Span {
+                    element: (key, value),
+                    range,
+                })
+            }
+        }
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields().is_empty()
+    }
+
+    /// All fields, in source order
+    pub fn fields(&self) -> Vec<Span<Field>> {
+        fn each<'a, T: 'a>(
+            spans: impl IntoIterator<Item = &'a Span<T>>,
+            f: impl Fn(&T) -> Field,
+        ) -> impl Iterator<Item = Span<Field>> {
+            spans.into_iter().map(move |s| Span {
+                element: f(&s.element),
+                range: s.range.clone(),
+            })
+        }
+
+        let mut fields: Vec<_> = each(&self.tags, |t| Field::Tag(t.clone()))
+            .chain(each(&self.assignees, |a| Field::Assignee(a.clone())))
+            .chain(each(&self.projects, |p| Field::Project(p.clone())))
+            .chain(each(&self.contexts, |c| Field::Context(c.clone())))
+            .chain(each(&self.due, |d| Field::Due(*d)))
+            .chain(each(&self.created, |d| Field::Created(*d)))
+            .chain(each(&self.completed, |d| Field::Completed(*d)))
+            .chain(each(&self.priority, |p| Field::Priority(*p)))
+            .chain(each(&self.other, |(key, value)| Field::Other {
+                key: key.clone(),
+                value: value.clone(),
+            }))
+            .collect();
+        fields.sort_by_key(|f| f.range.start);
+        fields
+    }
+}
+
+/// A whitespace separated word of item text. Trailing `,`, `;` and `.` are not part of the word,
+/// so `#backend,` is read as the tag `backend`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word<'a> {
+    pub text: &'a str,
+    pub range: Range,
+}
+
+/// A syntax for metadata. Grammars are tried in order at each word of the item text, and the
+/// first one that reads a field there wins.
+pub trait Grammar: fmt::Debug + Send + Sync {
+    /// Reads a field starting at `words[index]`. Returns the field and the number of words it
+    /// spans.
+    fn read(&self, words: &[Word<'_>], index: usize) -> Option<(Field, usize)>;
+
+    /// Writes a field in this grammar, or `None` if the grammar has no syntax for it
+    fn write(&self, field: &Field) -> Option<String>;
+
+    /// Whether a new field goes before the item text instead of after it
+    fn leading(&self, field: &Field) -> bool {
+        let _ = field;
+        false
+    }
+}
+
+fn date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+/// A tag or a name, like the `backend` of `#backend`. Plain numbers are not names, so issue
+/// references like `#12` are not tags.
+fn name(s: &str) -> Option<String> {
+    let valid = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'))
+        && !s.chars().all(|c| c.is_ascii_digit());
+
+    valid.then(|| s.to_string())
+}
+
+/// A `key:value` pair. Keys start with a letter, and values can't start with `/` so URLs are not
+/// read as pairs.
+fn key_value(s: &str) -> Option<(&str, &str)> {
+    let (key, value) = s.split_once(':')?;
+    let valid = key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        && !value.is_empty()
+        && !value.starts_with('/');
+
+    valid.then_some((key, value))
+}
+
+/// The default grammar: `#tag`, `@assignee`, `!high`, `due:2026-11-01`, `created:` and `done:`
+/// dates, and any other `key:value` pair.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Inline;
+
+impl Grammar for Inline {
+    fn read(&self, words: &[Word<'_>], index: usize) -> Option<(Field, usize)> {
+        let word = words[index].text;
+
+        let field = if let Some(tag) = word.strip_prefix('#') {
+            Field::Tag(name(tag)?)
+        } else if let Some(assignee) = word.strip_prefix('@') {
+            Field::Assignee(name(assignee)?)
+        } else if let Some(priority) = word.strip_prefix('!') {
+            Field::Priority(priority.parse().ok()?)
+        } else {
+            match key_value(word)? {
+                ("due", value) => Field::Due(date(value)?),
+                ("created", value) => Field::Created(date(value)?),
+                ("done", value) => Field::Completed(date(value)?),
+                (key, value) => Field::Other {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                },
+            }
+        };
+
+        Some((field, 1))
+    }
+
+    fn write(&self, field: &Field) -> Option<String> {
+        Some(match field {
+            Field::Tag(tag) => format!("#{tag}"),
+            Field::Assignee(name) => format!("@{name}"),
+            Field::Priority(priority) => format!("!{priority}"),
+            Field::Due(date) => format!("due:{date}"),
+            Field::Created(date) => format!("created:{date}"),
+            Field::Completed(date) => format!("done:{date}"),
+            Field::Other { key, value } => format!("{key}:{value}"),
+            Field::Project(_) | Field::Context(_) => return None,
+        })
+    }
+}
+
+/// The emoji format of the Obsidian Tasks plugin: `📅 2026-11-01` for due dates, `➕` and `✅`
+/// for created and done dates, `⏳` and `🛫` for scheduled and start dates, `🔺⏫🔼🔽⏬` for
+/// priorities, and `#tags`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObsidianTasks;
+
+const OBSIDIAN_PRIORITIES: [(&str, Priority); 5] = [
+    ("🔺", Priority::Highest),
+    ("⏫", Priority::High),
+    ("🔼", Priority::Medium),
+    ("🔽", Priority::Low),
+    ("⏬", Priority::Lowest),
+];
+
+const OBSIDIAN_DATES: [&str; 5] = ["📅", "➕", "✅", "⏳", "🛫"];
+
+impl Grammar for ObsidianTasks {
+    fn read(&self, words: &[Word<'_>], index: usize) -> Option<(Field, usize)> {
+        let word = words[index].text.trim_end_matches('\u{fe0f}');
+
+        if let Some(tag) = word.strip_prefix('#') {
+            return Some((Field::Tag(name(tag)?), 1));
+        }
+        if let Some((_, priority)) = OBSIDIAN_PRIORITIES.iter().find(|(e, _)| *e == word) {
+            return Some((Field::Priority(*priority), 1));
+        }
+
+        let emoji = OBSIDIAN_DATES.iter().find(|e| word.starts_with(**e))?;
+        let glued = word[emoji.len()..].trim_start_matches('\u{fe0f}');
+        let (value, count) = if glued.is_empty() {
+            (date(words.get(index + 1)?.text)?, 2)
+        } else {
+            (date(glued)?, 1)
+        };
+
+        let field = match *emoji {
+            "📅" => Field::Due(value),
+            "➕" => Field::Created(value),
+            "✅" => Field::Completed(value),
+            "⏳" => Field::Other {
+                key: "scheduled".to_string(),
+                value: value.to_string(),
+            },
+            _ => Field::Other {
+                key: "start".to_string(),
+                value: value.to_string(),
+            },
+        };
+
+        Some((field, count))
+    }
+
+    fn write(&self, field: &Field) -> Option<String> {
+        Some(match field {
+            Field::Tag(tag) => format!("#{tag}"),
+            Field::Due(date) => format!("📅 {date}"),
+            Field::Created(date) => format!("➕ {date}"),
+            Field::Completed(date) => format!("✅ {date}"),
+            Field::Priority(priority) => OBSIDIAN_PRIORITIES
+                .iter()
+                .find(|(_, p)| p == priority)?
+                .0
+                .to_string(),
+            Field::Other { key, value } if key == "scheduled" => format!("⏳ {value}"),
+            Field::Other { key, value } if key == "start" => format!("🛫 {value}"),
+            _ => return None,
+        })
+    }
+}
+
+/// The todo.txt format: `+project`, `@context`, `key:value` pairs with `due:` dates, and a
+/// `(A)` priority at the start of the text. Priorities `A` to `D` map to highest to low, and any
+/// later letter to lowest. Creation and completion dates, which todo.txt writes before the text,
+/// are `created:` and `done:` pairs, like in [`Inline`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TodoTxt;
+
+impl Grammar for TodoTxt {
+    fn read(&self, words: &[Word<'_>], index: usize) -> Option<(Field, usize)> {
+        let word = words[index].text;
+
+        let field = if let Some(project) = word.strip_prefix('+') {
+            Field::Project(name(project)?)
+        } else if let Some(context) = word.strip_prefix('@') {
+            Field::Context(name(context)?)
+        } else if let [b'(', letter @ b'A'..=b'Z', b')'] = word.as_bytes() {
+            if index != 0 {
+                return None;
+            }
+            Field::Priority(match letter {
+                b'A' => Priority::Highest,
+                b'B' => Priority::High,
+                b'C' => Priority::Medium,
+                b'D' => Priority::Low,
+                _ => Priority::Lowest,
+            })
+        } else {
+            match key_value(word)? {
+                ("due", value) => Field::Due(date(value)?),
+                ("created", value) => Field::Created(date(value)?),
+                ("done", value) => Field::Completed(date(value)?),
+                (key, value) => Field::Other {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                },
+            }
+        };
+
+        Some((field, 1))
+    }
+
+    fn write(&self, field: &Field) -> Option<String> {
+        Some(match field {
+            Field::Project(name) => format!("+{name}"),
+            Field::Context(name) => format!("@{name}"),
+            Field::Due(date) => format!("due:{date}"),
+            Field::Created(date) => format!("created:{date}"),
+            Field::Completed(date) => format!("done:{date}"),
+            Field::Priority(priority) => {
+                let letter = match priority {
+                    Priority::Highest => 'A',
+                    Priority::High => 'B',
+                    Priority::Medium => 'C',
+                    Priority::Low => 'D',
+                    Priority::Lowest => 'E',
+                };
+                format!("({letter})")
+            }
+            Field::Other { key, value } => format!("{key}:{value}"),
+            _ => return None,
+        })
+    }
+
+    fn leading(&self, field: &Field) -> bool {
+        matches!(field, Field::Priority(_))
+    }
+}
+
+/// Splits ranges of plain text into words
+pub(crate) fn words(source: &str, ranges: impl IntoIterator<Item = Range>) -> Vec<Word<'_>> {
+    let mut words = vec![];
+
+    for range in ranges {
+        let text = &source[range.clone()];
+        let mut start = None;
+
+        for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (start, c.is_whitespace()) {
+                (Some(s), true) => {
+                    let word = text[s..i].trim_end_matches([',', ';', '.']);
+                    if !word.is_empty() {
+                        let word_start = range.start + s;
+                        words.push(Word {
+                            text: word,
+                            range: word_start..word_start + word.len(),
+                        });
+                    }
+                    start = None;
+                }
+                (None, false) => start = Some(i),
+                _ => {}
+            }
+        }
+    }
+
+    words
+}
+
+/// Reads the metadata of an item from the ranges of its plain text
+pub(crate) fn scan(
+    source: &str,
+    ranges: impl IntoIterator<Item = Range>,
+    grammars: &[std::sync::Arc<dyn Grammar>],
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Metadata {
+    let words = words(source, ranges);
+    let mut metadata = Metadata::default();
+
+    let mut i = 0;
+    while i < words.len() {
+        match grammars.iter().find_map(|g| g.read(&words, i)) {
+            Some((field, count)) if count > 0 && i + count <= words.len() => {
+                let range = words[i].range.start..words[i + count - 1].range.end;
+                if !metadata.push(field, range.clone()) {
+                    diagnostics.push(Diagnostic::warning(
+                        range,
+                        "field given more than once, only the first one is used",
+                    ));
+                }
+                i += count;
+            }
+            _ => i += 1,
+        }
+    }
+
+    metadata
+}
+
+/// The grammar that wrote the token at `range`, if it reads as a field of the same kind as
+/// `field`
+pub(crate) fn grammar_of<'a>(
+    source: &str,
+    range: Range,
+    field: &Field,
+    grammars: &'a [std::sync::Arc<dyn Grammar>],
+) -> Option<&'a dyn Grammar> {
+    let words = words(source, [range]);
+
+    grammars
+        .iter()
+        .map(|g| g.as_ref())
+        .find(|g| g.read(&words, 0).is_some_and(|(f, _)| f.same_kind(field)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, ParseOptions};
+    use std::sync::Arc;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn metadata(source: &str, grammars: Vec<Arc<dyn Grammar>>) -> Metadata {
+        let options = ParseOptions {
+            grammars,
+            ..ParseOptions::default()
+        };
+        let (board, _) = Board::parse_with(source, &options);
+        let (_, item) = board.items().into_iter().next().unwrap();
+        item.element.metadata.clone()
+    }
+
+    #[test]
+    fn inline_fields() {
+        let source = "- [ ] ship it @alice #backend, due:2026-11-01 !high est:3h `#code` #12\n";
+        let meta = metadata(source, vec![Arc::new(Inline)]);
+
+        let tags: Vec<_> = meta.tags.iter().map(|t| t.element.as_str()).collect();
+        assert_eq!(tags, vec!["backend"]);
+        assert_eq!(&source[meta.tags[0].range.clone()], "#backend");
+        assert_eq!(meta.assignees[0].element, "alice");
+        let due = meta.due.unwrap();
+        assert_eq!(due.element, date("2026-11-01"));
+        assert_eq!(&source[due.range], "due:2026-11-01");
+        assert_eq!(meta.priority.unwrap().element, Priority::High);
+        assert_eq!(meta.other[0].element, ("est".to_string(), "3h".to_string()));
+    }
+
+    #[test]
+    fn urls_and_link_destinations_are_not_fields() {
+        let source = "- see https://example.com and [#docs](http://x.org/#a)\n";
+        let meta = metadata(source, vec![Arc::new(Inline)]);
+
+        assert!(meta.other.is_empty());
+        let tags: Vec<_> = meta.tags.iter().map(|t| t.element.as_str()).collect();
+        assert_eq!(tags, vec!["docs"]);
+    }
+
+    #[test]
+    fn obsidian_tasks_fields() {
+        let source = "- [ ] file taxes ⏫ 📅 2026-04-15 ➕2026-01-02 #home\n";
+        let meta = metadata(source, vec![Arc::new(ObsidianTasks)]);
+
+        assert_eq!(meta.priority.unwrap().element, Priority::High);
+        let due = meta.due.unwrap();
+        assert_eq!(due.element, date("2026-04-15"));
+        assert_eq!(&source[due.range], "📅 2026-04-15");
+        assert_eq!(meta.created.unwrap().element, date("2026-01-02"));
+        assert_eq!(meta.tags[0].element, "home");
+    }
+
+    #[test]
+    fn todo_txt_fields() {
+        let source = "- (B) call mom +family @phone due:2026-03-01 created:2026-02-01 (C)\n";
+        let meta = metadata(source, vec![Arc::new(TodoTxt)]);
+
+        // The second priority is not at the start, so it's just text
+        let priority = meta.priority.unwrap();
+        assert_eq!(priority.element, Priority::High);
+        assert_eq!(&source[priority.range], "(B)");
+        assert_eq!(meta.projects[0].element, "family");
+        assert_eq!(meta.contexts[0].element, "phone");
+        assert_eq!(meta.due.unwrap().element, date("2026-03-01"));
+        assert_eq!(meta.created.unwrap().element, date("2026-02-01"));
+    }
+
+    #[test]
+    fn grammars_are_tried_in_order() {
+        let source = "- @alice +web 🔼\n";
+        let grammars: Vec<Arc<dyn Grammar>> =
+            vec![Arc::new(TodoTxt), Arc::new(Inline), Arc::new(ObsidianTasks)];
+        let meta = metadata(source, grammars);
+
+        assert!(meta.assignees.is_empty());
+        assert_eq!(meta.contexts[0].element, "alice");
+        assert_eq!(meta.projects[0].element, "web");
+        assert_eq!(meta.priority.unwrap().element, Priority::Medium);
+    }
+
+    #[test]
+    fn fields_in_source_order() {
+        let source = "- !low task #a due:2026-01-01 #b\n";
+        let meta = metadata(source, vec![Arc::new(Inline)]);
+
+        let fields: Vec<_> = meta
+            .fields()
+            .into_iter()
+            .map(|f| &source[f.range])
+            .collect();
+        assert_eq!(fields, vec!["!low", "#a", "due:2026-01-01", "#b"]);
+    }
+
+    #[test]
+    fn repeated_fields_give_warnings() {
+        let source = "- task due:2026-01-01 due:2026-02-02\n";
+        let (board, diagnostics) = Board::parse(source);
+
+        let (_, item) = board.items().into_iter().next().unwrap();
+        let due = item.element.metadata.due.as_ref().unwrap();
+        assert_eq!(due.element, date("2026-01-01"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(&source[diagnostics[0].range.clone()], "due:2026-02-02");
+    }
+}
@@ -0,0 +1,525 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Board, Item, List, Span};
+
+/// Where a list is in the board: the top-level list `list` of section `section`, or, if `parents`
+/// is not empty, the nested list reached by following the item indexes in `parents` from there.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListPath {
+    pub section: usize,
+    pub list: usize,
+    pub parents: Vec<usize>,
+}
+
+/// Where an item is in the board: its list, and its index in that list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ItemPath {
+    pub list: ListPath,
+    pub index: usize,
+}
+
+impl ListPath {
+    pub fn new(section: usize, list: usize) -> Self {
+        ListPath {
+            section,
+            list,
+            parents: vec![],
+        }
+    }
+
+    pub fn item(&self, index: usize) -> ItemPath {
+        ItemPath {
+            list: self.clone(),
+            index,
+        }
+    }
+
+    /// The item that owns this list, or `None` for a top-level list
+    pub fn parent(&self) -> Option<ItemPath> {
+        let (&index, parents) = self.parents.split_last()?;
+
+        Some(ItemPath {
+            list: ListPath {
+                section: self.section,
+                list: self.list,
+                parents: parents.to_vec(),
+            },
+            index,
+        })
+    }
+
+    /// Whether this list is `item`'s nested list, or is nested anywhere below it
+    pub fn is_inside(&self, item: &ItemPath) -> bool {
+        self.section == item.list.section
+            && self.list == item.list.list
+            && self.parents.len() > item.list.parents.len()
+            && self.parents.starts_with(&item.list.parents)
+            && self.parents[item.list.parents.len()] == item.index
+    }
+}
+
+impl ItemPath {
+    pub fn new(section: usize, list: usize, index: usize) -> Self {
+        ListPath::new(section, list).item(index)
+    }
+
+    /// The path of this item's nested list (which may not exist)
+    pub fn nested(&self) -> ListPath {
+        let mut parents = self.list.parents.clone();
+        parents.push(self.index);
+
+        ListPath {
+            section: self.list.section,
+            list: self.list.list,
+            parents,
+        }
+    }
+
+    pub fn child(&self, index: usize) -> ItemPath {
+        self.nested().item(index)
+    }
+
+    /// Nesting depth, starting at 0 for items of top-level lists
+    pub fn depth(&self) -> usize {
+        self.list.parents.len()
+    }
+}
+
+impl Board {
+    pub fn list(&self, path: &ListPath) -> Option<&Span<List>> {
+        let mut list = self
+            .sections
+            .get(path.section)?
+            .element
+            .lists
+            .get(path.list)?;
+
+        for &index in &path.parents {
+            list = list
+                .element
+                .items
+                .get(index)?
+                .element
+                .nested_list
+                .as_ref()?;
+        }
+
+        Some(list)
+    }
+
+    pub fn item(&self, path: &ItemPath) -> Option<&Span<Item>> {
+        self.list(&path.list)?.element.items.get(path.index)
+    }
+
+    /// All items of the board, depth first, in source order
+    pub fn items(&self) -> Vec<(ItemPath, &Span<Item>)> {
+        fn walk<'a>(
+            list: &'a Span<List>,
+            path: ListPath,
+            out: &mut Vec<(ItemPath, &'a Span<Item>)>,
+        ) {
+            for (index, item) in list.element.items.iter().enumerate() {
+                let item_path = path.item(index);
+                if let Some(nested) = &item.element.nested_list {
+                    out.push((item_path.clone(), item));
+                    walk(nested, item_path.nested(), out);
+                } else {
+                    out.push((item_path, item));
+                }
+            }
+        }
+
+        let mut out = vec![];
+
+        for (s, section) in self.sections.iter().enumerate() {
+            for (l, list) in section.element.lists.iter().enumerate() {
+                walk(list, ListPath::new(s, l), &mut out);
+            }
+        }
+
+        out
+    }
+
+    /// The innermost item whose source range contains `offset`, so an offset in a nested item
+    /// gives the nested item rather than its parent
+    pub fn item_at(&self, offset: usize) -> Option<ItemPath> {
+        let contains = |item: &&Span<Item>| item.range.contains(&offset);
+
+        let (s, section) = self
+            .sections
+            .iter()
+            .enumerate()
+            .find(|(_, section)| section.range.contains(&offset))?;
+        let (l, mut list) = section
+            .element
+            .lists
+            .iter()
+            .enumerate()
+            .find(|(_, list)| list.range.contains(&offset))?;
+
+        let mut path = ListPath::new(s, l);
+
+        loop {
+            let (index, item) = list
+                .element
+                .items
+                .iter()
+                .enumerate()
+                .find(|(_, item)| contains(item))?;
+            let item_path = path.item(index);
+
+            match &item.element.nested_list {
+                Some(nested) if nested.element.items.iter().any(|item| contains(&item)) => {
+                    path = item_path.nested();
+                    list = nested;
+                }
+                _ => return Some(item_path),
+            }
+        }
+    }
+}
+
+impl fmt::Display for ListPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.section, self.list)?;
+        for index in &self.parents {
+            write!(f, ".{index}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Item paths are written as dot-separated indexes: section, list, then the item index in each
+/// nested list. `1.0.2.0` is the first nested item of the third item of the first list in the
+/// second section.
+impl fmt::Display for ItemPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.list, self.index)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePathError(String);
+
+impl fmt::Display for ParsePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path or id {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePathError {}
+
+fn parse_indexes(s: &str) -> Result<Vec<usize>, ParsePathError> {
+    s.split('.')
+        .map(|part| part.parse().map_err(|_| ParsePathError(s.to_string())))
+        .collect()
+}
+
+impl FromStr for ListPath {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_indexes(s)?.as_slice() {
+            [section, list, parents @ ..] => Ok(ListPath {
+                section: *section,
+                list: *list,
+                parents: parents.to_vec(),
+            }),
+            _ => Err(ParsePathError(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for ItemPath {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_indexes(s)?.as_slice() {
+            [section, list, parents @ .., index] => Ok(ItemPath {
+                list: ListPath {
+                    section: *section,
+                    list: *list,
+                    parents: parents.to_vec(),
+                },
+                index: *index,
+            }),
+            _ => Err(ParsePathError(s.to_string())),
+        }
+    }
+}
+
+/// An identity for an item that survives edits elsewhere in the file.
+///
+/// Items can carry an explicit anchor, written Obsidian-style as `^anchor` at the end of their
+/// text. Other items are identified by a hash of their text (whitespace-normalized, without the
+/// checkbox and the metadata fields), so toggling, moving, rescheduling or tagging an item keeps
+/// its id, but renaming it doesn't. Items with the same text are told apart by their order in the
+/// file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ItemId {
+    Anchor(String),
+    Hash { hash: u64, occurrence: usize },
+}
+
+impl fmt::Display for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemId::Anchor(anchor) => write!(f, "^{anchor}"),
+            ItemId::Hash {
+                hash,
+                occurrence: 0,
+            } => write!(f, "{hash:016x}"),
+            ItemId::Hash { hash, occurrence } => write!(f, "{hash:016x}-{occurrence}"),
+        }
+    }
+}
+
+impl FromStr for ItemId {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParsePathError(s.to_string());
+
+        if let Some(anchor) = s.strip_prefix('^') {
+            return is_anchor(anchor)
+                .then(|| ItemId::Anchor(anchor.to_string()))
+                .ok_or_else(error);
+        }
+
+        let (hash, occurrence) = s.split_once('-').unwrap_or((s, "0"));
+        if hash.len() != 16 {
+            return Err(error());
+        }
+
+        Ok(ItemId::Hash {
+            hash: u64::from_str_radix(hash, 16).map_err(|_| error())?,
+            occurrence: occurrence.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+fn is_anchor(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// The `^anchor` at the end of an item text, if any
+pub(crate) fn anchor(text: &str) -> Option<&str> {
+    let (before, anchor) = text.trim_end().rsplit_once('^')?;
+    (is_anchor(anchor) && (before.is_empty() || before.ends_with(char::is_whitespace)))
+        .then_some(anchor)
+}
+
+/// 64-bit FNV-1a, which unlike `std`'s hasher is stable across Rust versions and platforms
+pub(crate) fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn text_hash(text: &str) -> u64 {
+    let words = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    fnv1a(words.bytes())
+}
+
+/// A reference to an item that is meant to outlive the current parse: the path where the item
+/// was, and its id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemRef {
+    pub path: ItemPath,
+    pub id: Option<ItemId>,
+}
+
+impl Board {
+    /// Ids of all items, in the same order as [`Board::items`]
+    pub fn ids(&self, source: &str) -> Vec<(ItemPath, ItemId)> {
+        let mut seen: HashMap<u64, usize> = HashMap::new();
+
+        self.items()
+            .into_iter()
+            .map(|(path, item)| {
+                let text = item.text(source);
+                let id = match anchor(text) {
+                    Some(anchor) => ItemId::Anchor(anchor.to_string()),
+                    None => {
+                        let hash = text_hash(&item.text_without_fields(source));
+                        let occurrence = seen.entry(hash).or_default();
+                        *occurrence += 1;
+                        ItemId::Hash {
+                            hash,
+                            occurrence: *occurrence - 1,
+                        }
+                    }
+                };
+                (path, id)
+            })
+            .collect()
+    }
+
+    pub fn item_id(&self, source: &str, path: &ItemPath) -> Option<ItemId> {
+        self.ids(source)
+            .into_iter()
+            .find_map(|(p, id)| (p == *path).then_some(id))
+    }
+
+    /// Finds the item with the given id
+    pub fn find(&self, source: &str, id: &ItemId) -> Option<ItemPath> {
+        self.ids(source)
+            .into_iter()
+            .find_map(|(path, i)| (i == *id).then_some(path))
+    }
+
+    /// A reference to the item at `path`, to be resolved later, maybe on another parse
+    pub fn item_ref(&self, source: &str, path: &ItemPath) -> Option<ItemRef> {
+        self.item(path)?;
+
+        Some(ItemRef {
+            path: path.clone(),
+            id: self.item_id(source, path),
+        })
+    }
+
+    /// Finds a referenced item. Items are found by id when the reference has one, so that
+    /// references keep pointing to the same item when items before it are added or removed. If
+    /// it has no id, the path is used as is.
+    pub fn resolve(&self, source: &str, item: &ItemRef) -> Option<ItemPath> {
+        match &item.id {
+            Some(id) => self.find(source, id),
+            None => self.item(&item.path).map(|_| item.path.clone()),
+        }
+    }
+}
+
+/// Paths and ids are written as strings, like `1.0.2` and `^anchor`
+#[cfg(feature = "serde")]
+macro_rules! serde_as_string {
+    ($($ty:ty),*) => {$(
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "serde")]
+serde_as_string!(ListPath, ItemPath, ItemId);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+## Todo
+
+- [ ] first
+- [ ] same
+- [ ] same
+  - [ ] nested ^pinned
+";
+
+    #[test]
+    fn path_display_and_parse() {
+        let path = ItemPath::new(1, 0, 2).child(0);
+        assert_eq!(path.to_string(), "1.0.2.0");
+        assert_eq!("1.0.2.0".parse::<ItemPath>(), Ok(path));
+        assert_eq!("3.1".parse::<ListPath>(), Ok(ListPath::new(3, 1)));
+        assert!("1.0".parse::<ItemPath>().is_err());
+        assert!("1.x.0".parse::<ItemPath>().is_err());
+    }
+
+    #[test]
+    fn id_display_and_parse() {
+        for id in [
+            ItemId::Anchor("pinned".into()),
+            ItemId::Hash {
+                hash: 0xdeadbeef,
+                occurrence: 0,
+            },
+            ItemId::Hash {
+                hash: u64::MAX,
+                occurrence: 3,
+            },
+        ] {
+            assert_eq!(id.to_string().parse(), Ok(id));
+        }
+        assert!("^".parse::<ItemId>().is_err());
+        assert!("abc".parse::<ItemId>().is_err());
+    }
+
+    #[test]
+    fn item_at_offsets() {
+        let (board, _) = Board::parse(SOURCE);
+        let at = |text: &str| board.item_at(SOURCE.find(text).unwrap());
+
+        assert_eq!(at("## Todo"), None);
+        assert_eq!(at("first"), Some(ItemPath::new(0, 0, 0)));
+        assert_eq!(at("[ ] same\n  -"), Some(ItemPath::new(0, 0, 2)));
+        assert_eq!(at("nested"), Some(ItemPath::new(0, 0, 2).child(0)));
+        assert_eq!(board.item_at(SOURCE.len()), None);
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(anchor("task ^a-1"), Some("a-1"));
+        assert_eq!(anchor("^a"), Some("a"));
+        assert_eq!(anchor("2^10"), None);
+        assert_eq!(anchor("task ^"), None);
+        assert_eq!(anchor("task"), None);
+    }
+
+    #[test]
+    fn ids_tell_duplicates_apart() {
+        let (board, _) = Board::parse(SOURCE);
+        let ids: Vec<_> = board.ids(SOURCE).into_iter().map(|(_, id)| id).collect();
+
+        assert_eq!(ids.len(), 4);
+        assert_eq!(ids[3], ItemId::Anchor("pinned".into()));
+        let (
+            ItemId::Hash {
+                hash: a,
+                occurrence: 0,
+            },
+            ItemId::Hash {
+                hash: b,
+                occurrence: 1,
+            },
+        ) = (&ids[1], &ids[2])
+        else {
+            panic!("{ids:?}");
+        };
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn ids_survive_edits() {
+        let (board, _) = Board::parse(SOURCE);
+        let first = board.item_ref(SOURCE, &ItemPath::new(0, 0, 0)).unwrap();
+        let nested = board
+            .item_ref(SOURCE, &ItemPath::new(0, 0, 2).child(0))
+            .unwrap();
+
+        let source = format!("# Title\n\n## Todo\n\n- [ ] new\n{}", &SOURCE[9..]);
+        let source = source.replace("- [ ] first", "- [x] first");
+        let (board, _) = Board::parse(&source);
+
+        assert_eq!(board.resolve(&source, &first), Some(ItemPath::new(1, 0, 1)));
+        assert_eq!(
+            board.resolve(&source, &nested),
+            Some(ItemPath::new(1, 0, 3).child(0))
+        );
+
+        let renamed = source.replace("first", "renamed");
+        let (board, _) = Board::parse(&renamed);
+        assert_eq!(board.resolve(&renamed, &first), None);
+    }
+}
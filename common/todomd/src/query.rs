@@ -0,0 +1,687 @@
+//! A small language to find items, like `open and #backend and due < 2026-11-01`.
+//!
+//! | Query                   | Matches items                                               |
+//! |-------------------------|-------------------------------------------------------------|
+//! | `open`, `done`          | with an unchecked or a checked checkbox                     |
+//! | `#tag`                  | with the tag                                                |
+//! | `@name`                 | assigned to `name`, or with the todo.txt context `name`     |
+//! | `+project`              | with the todo.txt project                                   |
+//! | `section:"Operations"`  | in the section with this title, nested items included       |
+//! | `due:2026-11-01`        | due that day; also `created:` and `done:`                   |
+//! | `key:value`             | with this `key:value` pair                                  |
+//! | `due < 2026-11-01`      | due before the date; also `created` and `completed`         |
+//! | `priority >= high`      | with at least this priority                                 |
+//! | `due`, `priority`, ...  | that have the field                                         |
+//! | `word`, `"some words"`  | whose text contains the words, ignoring case                |
+//! | `parent(query)`         | nested in an item that matches `query`, at any depth        |
+//! | `child(query)`          | with a nested item that matches `query`, at any depth       |
+//!
+//! Comparisons are `<`, `<=`, `>`, `>=`, `=` and `!=`. Queries are combined with `not`, `and`
+//! and `or`, in this order of precedence, and grouped with parentheses. Queries written next to
+//! each other must all match, like with `and`. Names, titles and values are compared ignoring
+//! case. Every item is tested, nested ones included.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::{Board, Item, ItemPath, List, ListPath, Priority, Range, Span};
+
+/// Why a query can't be parsed, and where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    /// The column of the query where the error is, counting characters from 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Cmp {
+    fn holds<T: Ord>(self, left: T, right: T) -> bool {
+        match self {
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+            Cmp::Eq => left == right,
+            Cmp::Ne => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateField {
+    Due,
+    Created,
+    Completed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Open,
+    Done,
+    Tag(String),
+    Person(String),
+    Project(String),
+    Section(String),
+    Pair { key: String, value: String },
+    Text(String),
+    HasDate(DateField),
+    Date(DateField, Cmp, NaiveDate),
+    HasPriority,
+    Priority(Cmp, Priority),
+    Parent(Box<Expr>),
+    Child(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Cmp(Cmp),
+    Str(String),
+    Word(String),
+}
+
+/// A token, and the column it starts at
+type Located = (Token, usize);
+
+fn error<T>(column: usize, message: impl Into<String>) -> Result<T, QueryError> {
+    Err(QueryError {
+        column,
+        message: message.into(),
+    })
+}
+
+fn tokenize(query: &str) -> Result<Vec<Located>, QueryError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let column = i + 1;
+        let next = chars.get(i + 1).copied();
+
+        let (token, len) = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => (Token::Open, 1),
+            ')' => (Token::Close, 1),
+            '<' if next == Some('=') => (Token::Cmp(Cmp::Le), 2),
+            '<' => (Token::Cmp(Cmp::Lt), 1),
+            '>' if next == Some('=') => (Token::Cmp(Cmp::Ge), 2),
+            '>' => (Token::Cmp(Cmp::Gt), 1),
+            '=' => (Token::Cmp(Cmp::Eq), 1),
+            '!' if next == Some('=') => (Token::Cmp(Cmp::Ne), 2),
+            '"' => {
+                let mut text = String::new();
+                let mut end = i + 1;
+                loop {
+                    match chars.get(end) {
+                        None => return error(column, "unclosed quote"),
+                        Some('"') => break,
+                        Some('\\') if chars.get(end + 1).is_some() => {
+                            text.push(chars[end + 1]);
+                            end += 2;
+                        }
+                        Some(&c) => {
+                            text.push(c);
+                            end += 1;
+                        }
+                    }
+                }
+                (Token::Str(text), end + 1 - i)
+            }
+            _ => {
+                // `!` only ends a word when it starts `!=`
+                let mut end = i + 1;
+                while let Some(&c) = chars.get(end) {
+                    let ne = c == '!' && chars.get(end + 1) == Some(&'=');
+                    if ne || c.is_whitespace() || "()<>=\"".contains(c) {
+                        break;
+                    }
+                    end += 1;
+                }
+                (Token::Word(chars[i..end].iter().collect()), end - i)
+            }
+        };
+
+        tokens.push((token, column));
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Located>,
+    pos: usize,
+    /// The column right after the query, for errors at its end
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |&(_, column)| column)
+    }
+
+    fn next(&mut self) -> Option<Located> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.not()?;
+        loop {
+            if self.keyword("and") {
+                self.pos += 1;
+            } else if !self.starts_query() {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    /// Whether the next token starts another query, which is then joined with `and`
+    fn starts_query(&self) -> bool {
+        match self.peek() {
+            Some(Token::Open | Token::Str(_)) => true,
+            Some(Token::Word(_)) => !self.keyword("or"),
+            _ => false,
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if self.keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    /// The rest of a group, after its `(` at `column`
+    fn group(&mut self, column: usize) -> Result<Expr, QueryError> {
+        let expr = self.or()?;
+        match self.peek() {
+            Some(Token::Close) => {
+                self.pos += 1;
+                Ok(expr)
+            }
+            None => error(column, "unclosed `(`"),
+            Some(_) => error(self.column(), "expected `)`"),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        let column = self.column();
+        let Some((token, _)) = self.next() else {
+            return error(column, "expected a query");
+        };
+
+        let word = match token {
+            Token::Open => return self.group(column),
+            Token::Str(text) => return Ok(Expr::Text(text)),
+            Token::Close => return error(column, "unexpected `)`"),
+            Token::Cmp(_) => return error(column, "expected a query before the comparison"),
+            Token::Word(word) => word,
+        };
+
+        let name = |rest: &str, what: &str| match rest {
+            "" => error(column + 1, format!("expected a {what}")),
+            rest => Ok(rest.to_string()),
+        };
+        if let Some(tag) = word.strip_prefix('#') {
+            return Ok(Expr::Tag(name(tag, "tag")?));
+        }
+        if let Some(person) = word.strip_prefix('@') {
+            return Ok(Expr::Person(name(person, "name")?));
+        }
+        if let Some(project) = word.strip_prefix('+') {
+            return Ok(Expr::Project(name(project, "project")?));
+        }
+        if let Some((key, value)) = word.split_once(':') {
+            return self.pair(key, value, column);
+        }
+
+        match word.to_ascii_lowercase().as_str() {
+            "open" => Ok(Expr::Open),
+            "done" => Ok(Expr::Done),
+            "due" => self.date(DateField::Due),
+            "created" => self.date(DateField::Created),
+            "completed" => self.date(DateField::Completed),
+            "priority" => self.priority(),
+            keyword @ ("parent" | "child") => {
+                if self.peek() != Some(&Token::Open) {
+                    return error(self.column(), format!("expected `(` after `{keyword}`"));
+                }
+                let open = self.column();
+                self.pos += 1;
+                let inner = Box::new(self.group(open)?);
+                Ok(match keyword {
+                    "parent" => Expr::Parent(inner),
+                    _ => Expr::Child(inner),
+                })
+            }
+            keyword @ ("and" | "or" | "not") => {
+                error(column, format!("expected a query before `{keyword}`"))
+            }
+            _ => Ok(Expr::Text(word)),
+        }
+    }
+
+    /// A `key:value` pair. The value can be quoted, like `section:"Next week"`. Dates written as
+    /// fields, like `due:2026-11-01`, match items with that date.
+    fn pair(&mut self, key: &str, value: &str, column: usize) -> Result<Expr, QueryError> {
+        let (value, value_column) = match (value, self.peek()) {
+            ("", Some(Token::Str(_) | Token::Word(_))) => match self.next() {
+                Some((Token::Str(value) | Token::Word(value), value_column)) => {
+                    (value, value_column)
+                }
+                _ => unreachable!("the next token was just checked"),
+            },
+            ("", _) => return error(self.column(), format!("expected a value for `{key}`")),
+            (value, _) => (value.to_string(), column + key.chars().count() + 1),
+        };
+        if key.is_empty() {
+            return error(column, "expected a key before `:`");
+        }
+
+        let date = |field| match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+            Ok(date) => Ok(Expr::Date(field, Cmp::Eq, date)),
+            Err(_) => error(value_column, "expected a date like 2026-11-01"),
+        };
+
+        Ok(match key.to_ascii_lowercase().as_str() {
+            "section" => Expr::Section(value),
+            "text" => Expr::Text(value),
+            "due" => date(DateField::Due)?,
+            "created" => date(DateField::Created)?,
+            "done" | "completed" => date(DateField::Completed)?,
+            _ => Expr::Pair {
+                key: key.to_string(),
+                value,
+            },
+        })
+    }
+
+    fn comparison(&mut self) -> Option<(Cmp, Option<Located>)> {
+        match self.peek() {
+            Some(&Token::Cmp(cmp)) => {
+                self.pos += 1;
+                Some((cmp, self.next()))
+            }
+            _ => None,
+        }
+    }
+
+    fn date(&mut self, field: DateField) -> Result<Expr, QueryError> {
+        let Some((cmp, value)) = self.comparison() else {
+            return Ok(Expr::HasDate(field));
+        };
+
+        let column = value.as_ref().map_or(self.end, |v| v.1);
+        match value {
+            Some((Token::Word(value) | Token::Str(value), _)) => {
+                match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+                    Ok(date) => Ok(Expr::Date(field, cmp, date)),
+                    Err(_) => error(column, "expected a date like 2026-11-01"),
+                }
+            }
+            _ => error(column, "expected a date like 2026-11-01"),
+        }
+    }
+
+    fn priority(&mut self) -> Result<Expr, QueryError> {
+        let Some((cmp, value)) = self.comparison() else {
+            return Ok(Expr::HasPriority);
+        };
+
+        let column = value.as_ref().map_or(self.end, |v| v.1);
+        match value {
+            Some((Token::Word(value) | Token::Str(value), _)) => match value.parse() {
+                Ok(priority) => Ok(Expr::Priority(cmp, priority)),
+                Err(_) => error(
+                    column,
+                    "expected a priority: lowest, low, medium, high or highest",
+                ),
+            },
+            _ => error(
+                column,
+                "expected a priority: lowest, low, medium, high or highest",
+            ),
+        }
+    }
+}
+
+/// A parsed query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl std::str::FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+/// An item that matches a query: where it is in the board, and its range in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Match {
+    pub path: ItemPath,
+    pub range: Range,
+}
+
+/// What an item is tested against: the board, and where the item is in it
+struct Context<'a> {
+    board: &'a Board,
+    source: &'a str,
+    path: &'a ItemPath,
+    item: &'a Span<Item>,
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+impl Expr {
+    fn matches(&self, cx: &Context<'_>) -> bool {
+        let metadata = &cx.item.element.metadata;
+        let checkbox = cx.item.element.checkbox.as_ref().map(|c| c.element);
+        let date = |field: &DateField| match field {
+            DateField::Due => metadata.due.as_ref(),
+            DateField::Created => metadata.created.as_ref(),
+            DateField::Completed => metadata.completed.as_ref(),
+        };
+
+        match self {
+            Expr::And(a, b) => a.matches(cx) && b.matches(cx),
+            Expr::Or(a, b) => a.matches(cx) || b.matches(cx),
+            Expr::Not(a) => !a.matches(cx),
+            Expr::Open => checkbox == Some(false),
+            Expr::Done => checkbox == Some(true),
+            Expr::Tag(tag) => metadata.tags.iter().any(|t| same(&t.element, tag)),
+            Expr::Person(name) => metadata
+                .assignees
+                .iter()
+                .chain(&metadata.contexts)
+                .any(|p| same(&p.element, name)),
+            Expr::Project(name) => metadata.projects.iter().any(|p| same(&p.element, name)),
+            Expr::Section(title) => cx
+                .board
+                .sections
+                .get(cx.path.list.section)
+                .and_then(|section| section.title(cx.source))
+                .is_some_and(|t| same(t.trim(), title.trim())),
+            Expr::Pair { key, value } => metadata.other.iter().any(|pair| {
+                let (k, v) = &pair.element;
+                same(k, key) && same(v, value)
+            }),
+            Expr::Text(text) => cx
+                .item
+                .text(cx.source)
+                .to_lowercase()
+                .contains(&text.to_lowercase()),
+            Expr::HasDate(field) => date(field).is_some(),
+            Expr::Date(field, cmp, value) => {
+                date(field).is_some_and(|d| cmp.holds(d.element, *value))
+            }
+            Expr::HasPriority => metadata.priority.is_some(),
+            Expr::Priority(cmp, value) => metadata
+                .priority
+                .as_ref()
+                .is_some_and(|p| cmp.holds(p.element, *value)),
+            Expr::Parent(query) => {
+                let mut parent = cx.path.list.parent();
+                while let Some(path) = parent {
+                    let Some(item) = cx.board.item(&path) else {
+                        break;
+                    };
+                    if query.matches(&Context {
+                        path: &path,
+                        item,
+                        ..*cx
+                    }) {
+                        return true;
+                    }
+                    parent = path.list.parent();
+                }
+                false
+            }
+            Expr::Child(query) => match &cx.item.element.nested_list {
+                Some(nested) => query.matches_nested(cx, nested, &cx.path.nested()),
+                None => false,
+            },
+        }
+    }
+
+    /// Whether an item of `list`, at `path`, or an item nested in one of them matches
+    fn matches_nested(&self, cx: &Context<'_>, list: &Span<List>, path: &ListPath) -> bool {
+        list.element.items.iter().enumerate().any(|(index, item)| {
+            let path = path.item(index);
+            let cx = Context {
+                path: &path,
+                item,
+                ..*cx
+            };
+            self.matches(&cx)
+                || item
+                    .element
+                    .nested_list
+                    .as_ref()
+                    .is_some_and(|nested| self.matches_nested(&cx, nested, &path.nested()))
+        })
+    }
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Query, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(query)?,
+            pos: 0,
+            end: query.chars().count() + 1,
+        };
+
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Query { expr }),
+            Some(Token::Close) => error(parser.column(), "unexpected `)`"),
+            Some(_) => error(parser.column(), "expected `and` or `or`"),
+        }
+    }
+
+    /// Whether the item at `path` matches
+    pub fn matches(&self, board: &Board, source: &str, path: &ItemPath) -> bool {
+        board.item(path).is_some_and(|item| {
+            self.expr.matches(&Context {
+                board,
+                source,
+                path,
+                item,
+            })
+        })
+    }
+}
+
+impl Board {
+    /// The items that match `query`, nested ones included, in the order of [`Board::items`]
+    pub fn query(&self, source: &str, query: &Query) -> Vec<Match> {
+        self.items()
+            .into_iter()
+            .filter(|(path, item)| {
+                query.expr.matches(&Context {
+                    board: self,
+                    source,
+                    path,
+                    item,
+                })
+            })
+            .map(|(path, item)| Match {
+                range: item.range.clone(),
+                path,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+## Operations
+
+- [ ] deploy the API #backend @alice due:2026-10-20 !high
+  - [x] write the migration #backend due:2026-10-01
+  - [ ] update the docs
+- [x] rotate keys #security due:2026-12-01 estimate:3
+
+## Next week
+
+- [ ] plan the \"sprint\" #backend !low
+- call Bob
+";
+
+    fn found(query: &str) -> Vec<String> {
+        let (board, _) = Board::parse(SOURCE);
+        let query = Query::parse(query).unwrap();
+        board
+            .query(SOURCE, &query)
+            .into_iter()
+            .map(|m| m.path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn queries() {
+        assert_eq!(
+            found(r#"open and #backend and due < 2026-11-01 and section:"Operations""#),
+            ["0.0.0"]
+        );
+        assert_eq!(found("#backend"), ["0.0.0", "0.0.0.0", "1.0.0"]);
+        assert_eq!(found("#BACKEND done"), ["0.0.0.0"]);
+        assert_eq!(
+            found("not #backend and section:operations"),
+            ["0.0.0.1", "0.0.1"]
+        );
+        assert_eq!(found("@alice or #security"), ["0.0.0", "0.0.1"]);
+        assert_eq!(found("due >= 2026-10-20"), ["0.0.0", "0.0.1"]);
+        assert_eq!(found("priority > medium"), ["0.0.0"]);
+        assert_eq!(found("priority and not priority = low"), ["0.0.0"]);
+        assert_eq!(found("estimate:3"), ["0.0.1"]);
+        assert_eq!(found("due:2026-10-01"), ["0.0.0.0"]);
+        assert_eq!(found("DUE:\"2026-12-01\" or done:2026-12-01"), ["0.0.1"]);
+        assert_eq!(found(r#""the \"sprint\"""#), ["1.0.0"]);
+        assert_eq!(found("DOCS"), ["0.0.0.1"]);
+        assert_eq!(found("(open or done) and not due"), ["0.0.0.1", "1.0.0"]);
+        assert_eq!(found("parent(#backend)"), ["0.0.0.0", "0.0.0.1"]);
+        assert_eq!(found("child(done) and open"), ["0.0.0"]);
+        assert_eq!(
+            found("not parent(due) and not child(due)"),
+            ["0.0.1", "1.0.0", "1.0.1"]
+        );
+    }
+
+    #[test]
+    fn matches_give_source_ranges() {
+        let (board, _) = Board::parse(SOURCE);
+        let matches = board.query(SOURCE, &"rotate".parse().unwrap());
+
+        assert_eq!(matches.len(), 1);
+        assert!(SOURCE[matches[0].range.clone()].starts_with("- [x] rotate keys"));
+        assert!(
+            Query::parse("rotate")
+                .unwrap()
+                .matches(&board, SOURCE, &matches[0].path)
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_column() {
+        let error = |query: &str| {
+            let error = Query::parse(query).unwrap_err();
+            (error.column, error.message)
+        };
+
+        assert_eq!(error("due < tomorrow").0, 7);
+        assert_eq!(
+            error("open and priority = urgent"),
+            (
+                21,
+                "expected a priority: lowest, low, medium, high or highest".into()
+            )
+        );
+        assert_eq!(error("(open or done"), (1, "unclosed `(`".into()));
+        assert_eq!(error("open)"), (5, "unexpected `)`".into()));
+        assert_eq!(error("(open < 3)"), (7, "expected `)`".into()));
+        assert_eq!(error("open and"), (9, "expected a query".into()));
+        assert_eq!(error("\"unclosed"), (1, "unclosed quote".into()));
+        assert_eq!(error("#"), (2, "expected a tag".into()));
+        assert_eq!(
+            error("open due:2026-13-01"),
+            (10, "expected a date like 2026-11-01".into())
+        );
+        assert_eq!(
+            error("section:"),
+            (9, "expected a value for `section`".into())
+        );
+        assert_eq!(
+            error("parent #x"),
+            (8, "expected `(` after `parent`".into())
+        );
+        assert_eq!(error("open < 3"), (6, "expected `and` or `or`".into()));
+        assert_eq!(
+            error("é and or"),
+            (7, "expected a query before `or`".into())
+        );
+        assert_eq!(
+            Query::parse("due <").unwrap_err().to_string(),
+            "expected a date like 2026-11-01 at column 6"
+        );
+    }
+}
@@ -1,3 +1,6 @@
+// Vectors of ranges are the whole point of this module
+#![allow(clippy::single_range_in_vec_init)]
+
 use std::borrow::Borrow;
 use std::ops::Bound;
 
@@ -41,22 +44,24 @@ impl RangeExt for Range {
         assert!(!self.is_empty());
         assert!(!range.is_empty());
         if self.end < range.start || self.start > range.end {
-            return vec![self.clone()];
+            vec![self.clone()]
         } else if self.start < range.start && self.end > range.end {
-            return vec![self.start..range.start, range.end..self.end];
+            vec![self.start..range.start, range.end..self.end]
         } else if self.start < range.start {
-            return vec![self.start..range.start];
+            vec![self.start..range.start]
         } else if self.end > range.end {
-            return vec![range.end..self.end];
+            vec![range.end..self.end]
         } else {
-            return vec![];
+            vec![]
         }
     }
 }
 
 /// A set of non-overlapping, non-adjacent ranges. Inserting new ranges will merge them if
 /// possible. Ranges are kept sorted.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<Range>", into = "Vec<Range>"))]
 pub struct RangeSet {
     contents: Vec<Range>,
 }
@@ -67,6 +72,12 @@ impl From<Vec<Range>> for RangeSet {
     }
 }
 
+impl From<RangeSet> for Vec<Range> {
+    fn from(set: RangeSet) -> Self {
+        set.contents
+    }
+}
+
 impl FromIterator<Range> for RangeSet {
     fn from_iter<I: IntoIterator<Item = Range>>(iter: I) -> Self {
         let mut range = RangeSet::default();
@@ -170,7 +181,7 @@ impl<'a> IntoIterator for &'a RangeSet {
     }
 }
 
-impl<'a> IntoIterator for RangeSet {
+impl IntoIterator for RangeSet {
     type Item = Range;
     type IntoIter = std::vec::IntoIter<Range>;
 
@@ -0,0 +1,450 @@
+//! Keeping unsaved edits when the file changes under them.
+//!
+//! Operations address items by path, which only makes sense for the source they were made on.
+//! An [`OperationLog`] records each operation along with the ids of the items it targets (see
+//! [`ItemId`](crate::ItemId)), so that when another program changes the file, the operations can
+//! be applied again on the new source: each target is found again by id, and an operation whose
+//! target was changed or removed becomes a [`Conflict`] instead of editing the wrong item.
+
+use std::fmt;
+
+use crate::{Board, Diagnostic, EditError, ItemPath, ItemRef, ListPath, Operation, ParseOptions};
+
+/// Where a list was, in a form that can be found again in another version of the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+enum ListRef {
+    /// The nested list of an item
+    Nested(ItemRef),
+    /// A top-level list, in the section with this title if it has one
+    TopLevel {
+        title: Option<String>,
+        path: ListPath,
+    },
+}
+
+/// A logged operation, with its targets recorded by id
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Entry {
+    operation: Operation,
+    item: Option<ItemRef>,
+    list: Option<ListRef>,
+    /// The item that was at the index of the operation, which the new or moved item goes before.
+    /// `None` to go at the end of the list.
+    before: Option<ItemRef>,
+}
+
+/// Why a logged operation couldn't be applied again
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "detail", rename_all = "snake_case")
+)]
+pub enum ConflictReason {
+    /// The item was changed or removed
+    ItemNotFound(ItemRef),
+    /// The list was removed, or its section was renamed
+    ListNotFound(ListPath),
+    /// The operation doesn't apply to the new source, like checking an item that no longer has
+    /// a checkbox
+    Edit(EditError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conflict {
+    pub operation: Operation,
+    pub reason: ConflictReason,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            ConflictReason::ItemNotFound(item) => {
+                write!(f, "item {} was changed or removed", item.path)
+            }
+            ConflictReason::ListNotFound(list) => write!(f, "list {list} was removed"),
+            ConflictReason::Edit(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Operations applied to a board since it was last saved, in order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationLog {
+    entries: Vec<Entry>,
+    /// Entries taken out by [`OperationLog::undo`], last undone last
+    undone: Vec<Entry>,
+}
+
+/// The result of [`OperationLog::rebase`]
+#[derive(Debug)]
+pub struct Rebased {
+    pub source: String,
+    pub board: Board,
+    pub diagnostics: Vec<Diagnostic>,
+    /// The operations that were applied, recorded against the new source
+    pub log: OperationLog,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl OperationLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.undone.clear();
+    }
+
+    /// Takes the last operation out of the log, when its edit was undone. Returns whether there
+    /// was one.
+    pub fn undo(&mut self) -> bool {
+        match self.entries.pop() {
+            Some(entry) => {
+                self.undone.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Puts the last operation taken out by [`OperationLog::undo`] back, when its edit was redone.
+    /// Returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(entry) => {
+                self.entries.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn operations(&self) -> impl Iterator<Item = &Operation> {
+        self.entries.iter().map(|entry| &entry.operation)
+    }
+
+    /// Records an operation, before it's applied to `board`, which was parsed from `source`
+    pub fn record(&mut self, board: &Board, source: &str, operation: &Operation) {
+        let mut operation = operation.clone();
+
+        let item = target_item(&mut operation).and_then(|path| board.item_ref(source, path));
+
+        let (list, before) = match target_list(&mut operation) {
+            Some((path, index)) => {
+                let list = match path.parent() {
+                    Some(parent) => board.item_ref(source, &parent).map(ListRef::Nested),
+                    None => Some(ListRef::TopLevel {
+                        title: board
+                            .sections
+                            .get(path.section)
+                            .and_then(|s| s.title(source))
+                            .map(String::from),
+                        path: path.clone(),
+                    }),
+                };
+                (list, board.item_ref(source, &path.item(*index)))
+            }
+            None => (None, None),
+        };
+
+        self.undone.clear();
+        self.entries.push(Entry {
+            operation,
+            item,
+            list,
+            before,
+        });
+    }
+
+    /// Applies the logged operations again, in order, on `source`: usually the file as another
+    /// program left it. Operations whose targets can't be found are left out and returned as
+    /// conflicts.
+    pub fn rebase(&self, source: &str, options: &ParseOptions) -> Rebased {
+        let (mut board, mut diagnostics) = Board::parse_with(source, options);
+        let mut source = source.to_string();
+        let mut log = OperationLog::new();
+        let mut conflicts = vec![];
+
+        for entry in &self.entries {
+            let edit = entry.resolve(&board, &source).and_then(|operation| {
+                match board.edit(&source, &operation) {
+                    Ok(edit) => Ok((operation, edit)),
+                    Err(e) => Err(ConflictReason::Edit(e)),
+                }
+            });
+
+            match edit {
+                Ok((operation, edit)) => {
+                    log.record(&board, &source, &operation);
+                    source = edit.apply(&source);
+                    (board, diagnostics) = Board::parse_with(&source, &board.options);
+                }
+                Err(reason) => conflicts.push(Conflict {
+                    operation: entry.operation.clone(),
+                    reason,
+                }),
+            }
+        }
+
+        Rebased {
+            source,
+            board,
+            diagnostics,
+            log,
+            conflicts,
+        }
+    }
+}
+
+impl Entry {
+    /// The operation, with its paths pointing to where its targets are in `board`
+    fn resolve(&self, board: &Board, source: &str) -> Result<Operation, ConflictReason> {
+        let mut operation = self.operation.clone();
+
+        if let (Some(path), Some(item)) = (target_item(&mut operation), &self.item) {
+            *path = board
+                .resolve(source, item)
+                .ok_or_else(|| ConflictReason::ItemNotFound(item.clone()))?;
+        }
+
+        if let (Some((path, index)), Some(list)) = (target_list(&mut operation), &self.list) {
+            *path = match list {
+                ListRef::Nested(parent) => board
+                    .resolve(source, parent)
+                    .ok_or_else(|| ConflictReason::ItemNotFound(parent.clone()))?
+                    .nested(),
+                ListRef::TopLevel { title, path } => {
+                    let section = match title {
+                        Some(title) => board
+                            .sections
+                            .iter()
+                            .position(|s| s.title(source) == Some(title.as_str())),
+                        None => (path.section < board.sections.len()).then_some(path.section),
+                    };
+                    let section =
+                        section.ok_or_else(|| ConflictReason::ListNotFound(path.clone()))?;
+                    ListPath::new(section, path.list)
+                }
+            };
+
+            *index = match &self.before {
+                Some(before) => {
+                    let found = board
+                        .resolve(source, before)
+                        .filter(|found| found.list == *path)
+                        .ok_or_else(|| ConflictReason::ItemNotFound(before.clone()))?;
+                    found.index
+                }
+                None => board.list(path).map_or(0, |list| list.element.items.len()),
+            };
+        }
+
+        Ok(operation)
+    }
+}
+
+/// The item an operation acts on
+fn target_item(operation: &mut Operation) -> Option<&mut ItemPath> {
+    match operation {
+        Operation::SetChecked { item, .. }
+        | Operation::AddCheckbox { item, .. }
+        | Operation::Rename { item, .. }
+        | Operation::ReplaceText { item, .. }
+        | Operation::Delete { item }
+        | Operation::Move { item, .. }
+        | Operation::SetDue { item, .. }
+        | Operation::SetPriority { item, .. }
+        | Operation::AddTag { item, .. }
+        | Operation::RemoveTag { item, .. }
+        | Operation::AddAssignee { item, .. }
+        | Operation::RemoveAssignee { item, .. } => Some(item),
+        Operation::Insert { .. } => None,
+    }
+}
+
+/// The list and index an operation puts an item at
+fn target_list(operation: &mut Operation) -> Option<(&mut ListPath, &mut usize)> {
+    match operation {
+        Operation::Insert { list, index, .. } | Operation::Move { list, index, .. } => {
+            Some((list, index))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+## Todo
+
+- [ ] first
+- [ ] second
+  - [ ] nested
+
+## Done
+
+- [x] third
+";
+
+    /// Applies the operations to `SOURCE` and records them
+    fn log(operations: &[Operation]) -> (OperationLog, String) {
+        let mut log = OperationLog::new();
+        let mut source = SOURCE.to_string();
+
+        for operation in operations {
+            let (board, _) = Board::parse(&source);
+            log.record(&board, &source, operation);
+            source = board.edit(&source, operation).unwrap().apply(&source);
+        }
+
+        (log, source)
+    }
+
+    #[test]
+    fn operations_follow_their_items() {
+        let (log, _) = log(&[
+            Operation::SetChecked {
+                item: ItemPath::new(0, 0, 1),
+                checked: true,
+            },
+            Operation::Insert {
+                list: ListPath::new(0, 0),
+                index: 1,
+                text: "inserted".into(),
+                checkbox: Some(false),
+            },
+        ]);
+
+        // Someone else added an item at the top, and a section before the list
+        let changed = SOURCE
+            .replace("## Todo\n\n", "## Todo\n\n- [ ] zeroth\n")
+            .replace("## Done", "## Later\n\n- [ ] later\n\n## Done");
+        let rebased = log.rebase(&changed, &ParseOptions::default());
+
+        assert_eq!(rebased.conflicts, []);
+        assert_eq!(
+            rebased.source,
+            changed.replace(
+                "- [ ] first\n- [ ] second",
+                "- [ ] first\n- [ ] inserted\n- [x] second"
+            )
+        );
+        assert_eq!(rebased.log.len(), 2);
+    }
+
+    #[test]
+    fn operations_on_new_items_follow_them() {
+        let (log, _) = log(&[
+            Operation::Insert {
+                list: ListPath::new(1, 0),
+                index: 1,
+                text: "new".into(),
+                checkbox: Some(false),
+            },
+            Operation::SetChecked {
+                item: ItemPath::new(1, 0, 1),
+                checked: true,
+            },
+        ]);
+
+        let changed = SOURCE.replace("- [x] third\n", "- [x] third\n- [x] fourth\n");
+        let rebased = log.rebase(&changed, &ParseOptions::default());
+
+        assert_eq!(rebased.conflicts, []);
+        assert_eq!(
+            rebased.source,
+            format!("{changed}- [x] new\n"),
+            "new items go at the end, since they were appended"
+        );
+    }
+
+    #[test]
+    fn changed_items_are_conflicts() {
+        let (log, _) = log(&[
+            Operation::Rename {
+                item: ItemPath::new(0, 0, 0),
+                text: "renamed".into(),
+            },
+            Operation::SetChecked {
+                item: ItemPath::new(0, 0, 1).child(0),
+                checked: true,
+            },
+        ]);
+
+        let changed = SOURCE.replace("first", "first, edited elsewhere");
+        let rebased = log.rebase(&changed, &ParseOptions::default());
+
+        assert_eq!(
+            rebased.source,
+            changed.replace("- [ ] nested", "- [x] nested")
+        );
+        assert_eq!(rebased.conflicts.len(), 1);
+        assert!(matches!(
+            rebased.conflicts[0].reason,
+            ConflictReason::ItemNotFound(_)
+        ));
+        assert!(matches!(
+            rebased.conflicts[0].operation,
+            Operation::Rename { .. }
+        ));
+    }
+
+    #[test]
+    fn rebasing_on_the_same_source_gives_the_same_result() {
+        let operations = [
+            Operation::Move {
+                item: ItemPath::new(0, 0, 0),
+                list: ListPath::new(1, 0),
+                index: 0,
+            },
+            Operation::Delete {
+                item: ItemPath::new(0, 0, 0).child(0),
+            },
+        ];
+        let (log, edited) = log(&operations);
+        let rebased = log.rebase(SOURCE, &ParseOptions::default());
+
+        assert_eq!(rebased.conflicts, []);
+        assert_eq!(rebased.source, edited);
+        assert_eq!(rebased.log, log);
+    }
+
+    #[test]
+    fn undone_operations_are_not_applied_again() {
+        let check = Operation::SetChecked {
+            item: ItemPath::new(0, 0, 0),
+            checked: true,
+        };
+        let (mut log, _) = log(&[
+            check.clone(),
+            Operation::Delete {
+                item: ItemPath::new(1, 0, 0),
+            },
+        ]);
+
+        assert!(log.undo());
+        let rebased = log.rebase(SOURCE, &ParseOptions::default());
+        assert_eq!(rebased.source, SOURCE.replace("- [ ] first", "- [x] first"));
+
+        assert!(log.redo());
+        assert!(!log.redo());
+        assert_eq!(log.len(), 2);
+    }
+}
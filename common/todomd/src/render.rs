@@ -0,0 +1,234 @@
+//! Rendering boards as text, HTML or reports.
+//!
+//! [`Board::render`] walks the sections, lists and items of a board in source order, and calls
+//! the methods of a [`Renderer`] as it enters and leaves each of them. Renderers only implement
+//! the methods they need, and write to the output string they are given.
+
+mod html;
+mod report;
+
+use std::fmt::Write;
+
+pub use html::Html;
+pub use report::Report;
+
+use crate::{Board, Item, List, Section, Span};
+
+/// A format boards can be rendered to. Lists and items get their `depth`, which is 0 for the
+/// lists of a section and their items, 1 for the lists nested in those items, and so on.
+pub trait Renderer {
+    fn start_board(&mut self, out: &mut String, board: &Board, source: &str) {}
+    fn end_board(&mut self, out: &mut String, board: &Board, source: &str) {}
+
+    fn start_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {}
+    fn end_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {}
+
+    fn start_list(&mut self, out: &mut String, list: &Span<List>, depth: usize, source: &str) {}
+    fn end_list(&mut self, out: &mut String, list: &Span<List>, depth: usize, source: &str) {}
+
+    /// Called before the nested list of the item, if it has one
+    fn start_item(&mut self, out: &mut String, item: &Span<Item>, depth: usize, source: &str) {}
+    /// Called after the nested list of the item, if it has one
+    fn end_item(&mut self, out: &mut String, item: &Span<Item>, depth: usize, source: &str) {}
+}
+
+impl Board {
+    pub fn render(&self, source: &str, renderer: &mut dyn Renderer) -> String {
+        fn list(
+            out: &mut String,
+            renderer: &mut dyn Renderer,
+            list_span: &Span<List>,
+            depth: usize,
+            source: &str,
+        ) {
+            renderer.start_list(out, list_span, depth, source);
+            for item in &list_span.element.items {
+                renderer.start_item(out, item, depth, source);
+                if let Some(nested) = &item.element.nested_list {
+                    list(out, renderer, nested, depth + 1, source);
+                }
+                renderer.end_item(out, item, depth, source);
+            }
+            renderer.end_list(out, list_span, depth, source);
+        }
+
+        let mut out = String::new();
+
+        renderer.start_board(&mut out, self, source);
+        for section in &self.sections {
+            renderer.start_section(&mut out, section, source);
+            for section_list in &section.element.lists {
+                list(&mut out, renderer, section_list, 0, source);
+            }
+            renderer.end_section(&mut out, section, source);
+        }
+        renderer.end_board(&mut out, self, source);
+
+        out
+    }
+
+    /// The title of the board: the first heading above the section level that has no lists
+    /// under it, like the `# Project` of a todomd file
+    pub fn title<'a>(&self, source: &'a str) -> Option<&'a str> {
+        let section_level = self.options.section_level.unwrap_or_default();
+
+        self.sections
+            .iter()
+            .filter(|section| !has_items(section))
+            .filter_map(|section| section.element.heading.as_ref())
+            .find(|heading| heading.element.level < section_level)
+            .map(|heading| heading.title(source).trim())
+    }
+}
+
+fn has_items(section: &Span<Section>) -> bool {
+    section
+        .element
+        .lists
+        .iter()
+        .any(|list| !list.element.items.is_empty())
+}
+
+/// How many items of `lists` and their nested lists are checked, and how many have a checkbox
+fn progress<'a>(lists: impl IntoIterator<Item = &'a Span<List>>) -> (usize, usize) {
+    let mut items: Vec<&Span<Item>> = lists
+        .into_iter()
+        .flat_map(|list| &list.element.items)
+        .collect();
+    let (mut done, mut total) = (0, 0);
+
+    while let Some(item) = items.pop() {
+        if let Some(checkbox) = &item.element.checkbox {
+            total += 1;
+            done += usize::from(checkbox.element);
+        }
+        if let Some(nested) = &item.element.nested_list {
+            items.extend(&nested.element.items);
+        }
+    }
+
+    (done, total)
+}
+
+/// The share of done items, rounded down so that only finished work shows as 100%
+fn percent(done: usize, total: usize) -> usize {
+    (done * 100).checked_div(total).unwrap_or(0)
+}
+
+/// How a board is parsed: each section with its heading, and each item with its checkbox and
+/// the debug form of its content ranges. With `verbose`, also the metadata fields and the source
+/// of each item and list, and the prose of each section.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Outline {
+    pub verbose: bool,
+}
+
+impl Outline {
+    pub fn new(verbose: bool) -> Self {
+        Outline { verbose }
+    }
+}
+
+impl Renderer for Outline {
+    fn start_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {
+        if let Some(heading) = &section.element.heading {
+            let level = "#".repeat(heading.element.level as usize);
+            writeln!(out, "{level} {}\n", heading.title(source)).unwrap();
+        }
+    }
+
+    fn end_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {
+        if self.verbose {
+            for range in &section.element.prose {
+                writeln!(out, "(prose: {:?})", &source[range.clone()]).unwrap();
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+
+    fn end_list(&mut self, out: &mut String, list: &Span<List>, depth: usize, source: &str) {
+        if self.verbose {
+            writeln!(out, "(list source: {:?})\n", &source[list.range.clone()]).unwrap();
+        }
+        if depth == 0 {
+            out.push('\n');
+        }
+    }
+
+    fn start_item(&mut self, out: &mut String, item: &Span<Item>, depth: usize, source: &str) {
+        let indent = depth * 2;
+        let checkbox = item.element.checkbox.as_ref().map(|c| c.element);
+
+        writeln!(out, "{:indent$} - {checkbox:?}", "").unwrap();
+        for range in &item.element.contents {
+            writeln!(
+                out,
+                "{:indent$}{:?}",
+                "",
+                &source[range.clone()],
+                indent = indent + 2
+            )
+            .unwrap();
+        }
+    }
+
+    fn end_item(&mut self, out: &mut String, item: &Span<Item>, depth: usize, source: &str) {
+        if self.verbose {
+            for field in item.element.metadata.fields() {
+                writeln!(out, "(field: {:?} at {:?})", field.element, field.range).unwrap();
+            }
+            writeln!(out, "(item source: {:?})\n", &source[item.range.clone()]).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outline() {
+        let source = "# Project\n\n## Todo\n\n- [ ] first #tag\n  - nested\n- [x] second\n";
+        let (board, _) = Board::parse(source);
+
+        assert_eq!(
+            board.render(source, &mut Outline::new(false)),
+            "\
+# Project
+
+
+## Todo
+
+ - Some(false)
+  \"first #tag\"
+   - None
+    \"nested\"
+ - Some(true)
+  \"second\"
+
+
+"
+        );
+
+        let verbose = board.render(source, &mut Outline::new(true));
+        assert!(verbose.contains("(field: Tag(\"tag\") at 32..36)"));
+        assert!(verbose.contains("(item source: \"- [x] second\\n\")"));
+    }
+
+    #[test]
+    fn titles_and_progress() {
+        let source = "# Project\n\n## Todo\n\n- [ ] a\n  - [x] b\n  - c\n\n## Done\n\n- [x] d\n";
+        let (board, _) = Board::parse(source);
+
+        assert_eq!(board.title(source), Some("Project"));
+        let lists = |i: usize| &board.sections[i].element.lists;
+        assert_eq!(progress(lists(1)), (1, 2));
+        assert_eq!(progress(lists(2)), (1, 1));
+        assert_eq!(percent(1, 3), 33);
+        assert_eq!(percent(0, 0), 0);
+
+        let (board, _) = Board::parse("## Todo\n\n- [ ] a\n");
+        assert_eq!(board.title("## Todo\n\n- [ ] a\n"), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! A self-contained HTML page that shows the board as columns of cards. The styles are inline and
+//! there is no script, so the page can be published as is, or opened from a file.
+
+use std::fmt::Write;
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd, html};
+
+use super::{Renderer, has_items, percent, progress};
+use crate::{Board, Field, Item, List, Section, Span};
+
+const STYLE: &str = r#"
+:root {
+  --bg: #f4f5f7; --column: #ebecf0; --card: #fff; --text: #172b4d; --muted: #5e6c84;
+  --accent: #0c66e4; --border: #dfe1e6;
+  font-family: system-ui, -apple-system, "Segoe UI", sans-serif; color: var(--text);
+  background: var(--bg);
+}
+@media (prefers-color-scheme: dark) {
+  :root {
+    --bg: #1d2125; --column: #101204; --card: #22272b; --text: #b6c2cf; --muted: #8c9bab;
+    --accent: #579dff; --border: #384148;
+  }
+}
+body { margin: 0; padding: 1.5rem; }
+h1 { margin: 0 0 1rem; font-size: 1.5rem; }
+.board { display: flex; gap: 1rem; align-items: flex-start; overflow-x: auto; }
+.column { background: var(--column); border-radius: 8px; padding: 0.75rem; min-width: 16rem;
+  max-width: 22rem; flex: 0 0 auto; }
+.column h2 { display: flex; justify-content: space-between; gap: 0.5rem; margin: 0 0 0.75rem;
+  font-size: 1rem; }
+.column h2 .count { color: var(--muted); font-weight: normal; }
+ul { list-style: none; margin: 0; padding: 0; }
+.column > ul > li { background: var(--card); border: 1px solid var(--border); border-radius: 6px;
+  padding: 0.5rem 0.6rem; margin-bottom: 0.5rem; }
+li li { margin-top: 0.3rem; }
+li ul { margin-left: 1.2rem; }
+.done > .text { color: var(--muted); text-decoration: line-through; }
+input[type=checkbox] { margin: 0 0.4rem 0 0; vertical-align: middle; }
+.field { display: inline-block; font-size: 0.75rem; border-radius: 4px; padding: 0 0.35rem;
+  margin-left: 0.3rem; background: var(--column); color: var(--muted); }
+.field.tag { color: var(--accent); }
+.priority-high, .priority-highest { color: #c9372c; font-weight: bold; }
+a { color: var(--accent); }
+code { font-size: 0.85em; }
+"#;
+
+/// Escapes text for HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether a link can point at `url`: web and mail links, and relative ones
+fn safe_url(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => ["http", "https", "mailto"]
+            .iter()
+            .any(|safe| scheme.eq_ignore_ascii_case(safe)),
+        _ => true,
+    }
+}
+
+/// Inline markdown as HTML, without the paragraph around it. Raw HTML is shown as text, links to
+/// other schemes than http, https and mailto as their text, and images as their description, so
+/// the page loads nothing and runs nothing.
+fn inline_html(markdown: &str) -> String {
+    let mut unsafe_link = false;
+    let events = Parser::new(markdown).filter_map(|event| match event {
+        Event::Start(Tag::Paragraph) | Event::End(TagEnd::Paragraph) => None,
+        Event::Start(Tag::Image { .. }) | Event::End(TagEnd::Image) => None,
+        Event::Start(Tag::Link { ref dest_url, .. }) if !safe_url(dest_url) => {
+            unsafe_link = true;
+            None
+        }
+        Event::End(TagEnd::Link) if unsafe_link => {
+            unsafe_link = false;
+            None
+        }
+        Event::Html(html) | Event::InlineHtml(html) => Some(Event::Text(html)),
+        event => Some(event),
+    });
+
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out.trim_end().to_string()
+}
+
+/// A metadata field as a badge
+fn field(field: &Field) -> Option<String> {
+    let (class, text) = match field {
+        Field::Tag(tag) => ("tag", format!("#{tag}")),
+        Field::Assignee(name) => ("assignee", format!("@{name}")),
+        Field::Project(name) => ("project", format!("+{name}")),
+        Field::Context(name) => ("context", format!("@{name}")),
+        Field::Due(date) => ("due", format!("due {date}")),
+        Field::Completed(date) => ("completed", format!("done {date}")),
+        Field::Priority(priority) => {
+            return Some(format!(
+                r#"<span class="field priority priority-{priority}">{priority}</span>"#
+            ));
+        }
+        Field::Other { key, value } => ("other", format!("{key}: {value}")),
+        Field::Created(_) => return None,
+    };
+
+    Some(format!(
+        r#"<span class="field {class}">{}</span>"#,
+        escape(&text)
+    ))
+}
+
+/// The board as an HTML page, with a column for each section that has items. Item text is
+/// rendered from its markdown, and metadata fields are shown as badges after it.
+#[derive(Debug, Clone, Default)]
+pub struct Html {
+    /// Whether the current section is shown as a column
+    writing: bool,
+}
+
+impl Html {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Renderer for Html {
+    fn start_board(&mut self, out: &mut String, board: &Board, source: &str) {
+        let title = board.title(source);
+
+        out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        writeln!(out, "<title>{}</title>", escape(title.unwrap_or("TODO"))).unwrap();
+        writeln!(out, "<style>{STYLE}</style>\n</head>\n<body>").unwrap();
+        if let Some(title) = title {
+            writeln!(out, "<h1>{}</h1>", inline_html(title)).unwrap();
+        }
+        out.push_str("<main class=\"board\">\n");
+    }
+
+    fn end_board(&mut self, out: &mut String, board: &Board, source: &str) {
+        out.push_str("</main>\n</body>\n</html>\n");
+    }
+
+    fn start_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {
+        self.writing = has_items(section);
+        if !self.writing {
+            return;
+        }
+
+        let (done, total) = progress(&section.element.lists);
+        let title = section.title(source).map(str::trim).unwrap_or_default();
+        out.push_str("<section class=\"column\">\n");
+        writeln!(
+            out,
+            "<h2><span>{}</span> <span class=\"count\" title=\"{}% done\">{done}/{total}</span></h2>",
+            inline_html(title),
+            percent(done, total)
+        )
+        .unwrap();
+    }
+
+    fn end_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {
+        if self.writing {
+            out.push_str("</section>\n");
+        }
+    }
+
+    fn start_list(&mut self, out: &mut String, list: &Span<List>, depth: usize, source: &str) {
+        if self.writing {
+            out.push_str("<ul>\n");
+        }
+    }
+
+    fn end_list(&mut self, out: &mut String, list: &Span<List>, depth: usize, source: &str) {
+        if self.writing {
+            out.push_str("</ul>\n");
+        }
+    }
+
+    fn start_item(&mut self, out: &mut String, item: &Span<Item>, depth: usize, source: &str) {
+        if !self.writing {
+            return;
+        }
+
+        let checked = item.element.checkbox.as_ref().map(|c| c.element);
+        let class = match checked {
+            Some(true) => "done",
+            Some(false) => "open",
+            None => "note",
+        };
+        write!(out, "<li class=\"{class}\">").unwrap();
+        match checked {
+            Some(true) => out.push_str("<input type=\"checkbox\" checked disabled>"),
+            Some(false) => out.push_str("<input type=\"checkbox\" disabled>"),
+            None => {}
+        }
+        write!(
+            out,
+            "<span class=\"text\">{}</span>",
+            inline_html(&item.text_without_fields(source))
+        )
+        .unwrap();
+        for badge in item
+            .element
+            .metadata
+            .fields()
+            .iter()
+            .filter_map(|f| field(&f.element))
+        {
+            out.push_str(&badge);
+        }
+        out.push('\n');
+    }
+
+    fn end_item(&mut self, out: &mut String, item: &Span<Item>, depth: usize, source: &str) {
+        if self.writing {
+            out.push_str("</li>\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_page() {
+        let source = "\
+# My <project>
+
+## To do
+
+- [ ] pick a *font* #design !high due:2026-03-01
+  - [x] shortlist
+- notes <script>alert(1)</script>
+- [click](javascript:alert(1)) [docs](https://example.org/a?b) ![logo](https://example.org/logo.png)
+
+## Empty
+
+## Done
+
+- [x] hosting @alice
+";
+        let (board, _) = Board::parse(source);
+        let page = board.render(source, &mut Html::new());
+
+        assert!(page.starts_with("<!DOCTYPE html>\n"));
+        assert!(page.ends_with("</main>\n</body>\n</html>\n"));
+        assert!(page.contains("<title>My &lt;project&gt;</title>"));
+        assert!(!page.contains("<script"), "no scripts, even from the items");
+        assert_eq!(page.matches("<section class=\"column\">").count(), 2);
+        assert!(page.contains(
+            "<h2><span>To do</span> <span class=\"count\" title=\"50% done\">1/2</span></h2>"
+        ));
+        assert!(page.contains(concat!(
+            "<li class=\"open\"><input type=\"checkbox\" disabled>",
+            "<span class=\"text\">pick a <em>font</em></span>",
+            "<span class=\"field tag\">#design</span>",
+            "<span class=\"field priority priority-high\">high</span>",
+            "<span class=\"field due\">due 2026-03-01</span>\n<ul>\n",
+            "<li class=\"done\"><input type=\"checkbox\" checked disabled>",
+            "<span class=\"text\">shortlist</span>\n</li>\n</ul>\n</li>\n",
+        )));
+        assert!(
+            page.contains(
+                "<span class=\"text\">notes &lt;script&gt;alert(1)&lt;/script&gt;</span>"
+            )
+        );
+        assert!(!page.contains("javascript:"), "no script links");
+        assert!(!page.contains("<img"), "no remote images");
+        assert!(page.contains(concat!(
+            "<span class=\"text\">click ",
+            "<a href=\"https://example.org/a?b\">docs</a> logo</span>"
+        )));
+    }
+}
@@ -0,0 +1,161 @@
+//! A status report in markdown: a table with the progress of each section, then the items of
+//! each section under a heading that repeats it.
+
+use std::fmt::Write;
+
+use super::{Renderer, has_items, percent, progress};
+use crate::{Board, Item, List, Section, Span};
+
+/// A markdown status report. Items are counted, nested ones included, when they have a checkbox.
+/// Sections without items are left out, like the heading that holds the title of the board.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// Whether the items of the current section are written
+    writing: bool,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn section_title(section: &Span<Section>, source: &str) -> String {
+    match section.title(source).map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => "Untitled".to_string(),
+    }
+}
+
+fn summary(done: usize, total: usize) -> String {
+    format!("{done}/{total} done ({}%)", percent(done, total))
+}
+
+impl Renderer for Report {
+    fn start_board(&mut self, out: &mut String, board: &Board, source: &str) {
+        let title = board.title(source).unwrap_or("Status report");
+        writeln!(out, "# {title}\n").unwrap();
+
+        let sections: Vec<_> = board.sections.iter().filter(|s| has_items(s)).collect();
+        if sections.is_empty() {
+            writeln!(out, "No items.\n").unwrap();
+            return;
+        }
+
+        writeln!(out, "| Section | Done | Progress |").unwrap();
+        writeln!(out, "| --- | ---: | ---: |").unwrap();
+        for section in &sections {
+            let (done, total) = progress(&section.element.lists);
+            let title = section_title(section, source).replace('|', "\\|");
+            writeln!(
+                out,
+                "| {title} | {done}/{total} | {}% |",
+                percent(done, total)
+            )
+            .unwrap();
+        }
+        let (done, total) = progress(board.lists());
+        writeln!(
+            out,
+            "| **Total** | {done}/{total} | {}% |\n",
+            percent(done, total)
+        )
+        .unwrap();
+    }
+
+    fn end_board(&mut self, out: &mut String, board: &Board, source: &str) {
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    }
+
+    fn start_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {
+        self.writing = has_items(section);
+        if self.writing {
+            let (done, total) = progress(&section.element.lists);
+            let title = section_title(section, source);
+            writeln!(out, "## {title}\n\n{}\n", summary(done, total)).unwrap();
+        }
+    }
+
+    fn end_section(&mut self, out: &mut String, section: &Span<Section>, source: &str) {
+        if self.writing {
+            out.push('\n');
+        }
+    }
+
+    fn start_item(&mut self, out: &mut String, item: &Span<Item>, depth: usize, source: &str) {
+        if !self.writing {
+            return;
+        }
+
+        let checkbox = match item.element.checkbox.as_ref().map(|c| c.element) {
+            Some(true) => "[x] ",
+            Some(false) => "[ ] ",
+            None => "",
+        };
+        let text = item.text(source).split_whitespace().collect::<Vec<_>>();
+        writeln!(out, "{}- {checkbox}{}", "  ".repeat(depth), text.join(" ")).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let source = "\
+# Website
+
+## To do
+
+- [ ] pick a *font* #design
+  - [x] shortlist
+  - ask Bob
+- [ ] write copy
+
+## Done | shipped
+
+- [x] hosting
+
+## Notes
+
+Some prose.
+";
+        let (board, _) = Board::parse(source);
+
+        assert_eq!(
+            board.render(source, &mut Report::new()),
+            "\
+# Website
+
+| Section | Done | Progress |
+| --- | ---: | ---: |
+| To do | 1/3 | 33% |
+| Done \\| shipped | 1/1 | 100% |
+| **Total** | 2/4 | 50% |
+
+## To do
+
+1/3 done (33%)
+
+- [ ] pick a *font* #design
+  - [x] shortlist
+  - ask Bob
+- [ ] write copy
+
+## Done | shipped
+
+1/1 done (100%)
+
+- [x] hosting
+"
+        );
+
+        let (board, _) = Board::parse("# Empty\n");
+        assert_eq!(
+            board.render("# Empty\n", &mut Report::new()),
+            "# Empty\n\nNo items.\n"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Undo and redo, from the inverses of the edits that were applied.
+//!
+//! An [`UndoStack`] keeps each [`Edit`] along with its [`inverse`](Edit::inverse), which are exact
+//! byte splices: undoing gives back the source as it was, byte for byte. Splices only make sense
+//! on the source they were made for, so the stack keeps a hash of the whole source before and
+//! after each edit, and refuses to undo or redo on any other source. Checking only the spliced
+//! bytes isn't enough: text changed elsewhere can shift other bytes into their place.
+
+use crate::Edit;
+use crate::path::fnv1a;
+
+/// An edit, the edit that undoes it, and hashes of the source before and after the edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Change {
+    edit: Edit,
+    inverse: Edit,
+    /// Changes from journals written before the hashes were kept can't be undone
+    #[cfg_attr(feature = "serde", serde(default))]
+    before: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    after: u64,
+}
+
+fn hash(source: &str) -> u64 {
+    fnv1a(source.bytes())
+}
+
+/// The edits that were applied to a source, to undo them, and the ones that were undone, to redo
+/// them
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UndoStack {
+    done: Vec<Change>,
+    undone: Vec<Change>,
+    /// How many edits can be undone. The oldest ones are forgotten.
+    limit: usize,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        UndoStack {
+            done: vec![],
+            undone: vec![],
+            limit: 100,
+        }
+    }
+}
+
+impl UndoStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Forgets every edit, for when the source was replaced
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    /// Records an edit, before it's applied to `source`. The edits that were undone can no longer
+    /// be redone.
+    pub fn record(&mut self, source: &str, edit: &Edit) {
+        if edit.is_empty() {
+            return;
+        }
+
+        self.undone.clear();
+        self.done.push(Change {
+            edit: edit.clone(),
+            inverse: edit.inverse(source),
+            before: hash(source),
+            after: hash(&edit.apply(source)),
+        });
+
+        if self.done.len() > self.limit {
+            self.done.drain(..self.done.len() - self.limit);
+        }
+    }
+
+    /// Undoes the last edit, and returns the source as it was before it. Returns `None` if there
+    /// is nothing to undo, or if `source` is not what the edit left.
+    pub fn undo(&mut self, source: &str) -> Option<String> {
+        let change = self.done.last()?;
+        if hash(source) != change.after {
+            return None;
+        }
+
+        let source = change.inverse.apply(source);
+        self.undone.extend(self.done.pop());
+        Some(source)
+    }
+
+    /// Applies the last undone edit again, and returns the new source. Returns `None` if there is
+    /// nothing to redo, or if `source` is not what the undo left.
+    pub fn redo(&mut self, source: &str) -> Option<String> {
+        let change = self.undone.last()?;
+        if hash(source) != change.before {
+            return None;
+        }
+
+        let source = change.edit.apply(source);
+        self.done.extend(self.undone.pop());
+        Some(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, ItemPath, ListPath, Operation};
+
+    const SOURCE: &str = "- [ ] first\r\n- [ ] second\n  - [ ] nested\n\n- [x] done\n";
+
+    #[test]
+    fn undo_and_redo_give_the_same_bytes() {
+        let operations = [
+            Operation::SetChecked {
+                item: ItemPath::new(0, 0, 0),
+                checked: true,
+            },
+            Operation::Move {
+                item: ItemPath::new(0, 0, 1),
+                list: ListPath::new(0, 0),
+                index: 0,
+            },
+            Operation::Insert {
+                list: ListPath::new(0, 0),
+                index: 1,
+                text: "inserted".into(),
+                checkbox: Some(false),
+            },
+            Operation::Delete {
+                item: ItemPath::new(0, 0, 3),
+            },
+        ];
+
+        let mut stack = UndoStack::new();
+        let mut sources = vec![SOURCE.to_string()];
+        for operation in &operations {
+            let source = sources.last().unwrap();
+            let (board, _) = Board::parse(source);
+            let edit = board.edit(source, operation).unwrap();
+            stack.record(source, &edit);
+            sources.push(edit.apply(source));
+        }
+
+        let mut source = sources.last().unwrap().clone();
+        for expected in sources.iter().rev().skip(1) {
+            source = stack.undo(&source).unwrap();
+            assert_eq!(&source, expected);
+        }
+        assert!(!stack.can_undo());
+        assert_eq!(stack.undo(&source), None);
+
+        for expected in &sources[1..] {
+            source = stack.redo(&source).unwrap();
+            assert_eq!(&source, expected);
+        }
+        assert!(!stack.can_redo());
+    }
+
+    #[test]
+    fn new_edits_drop_the_undone_ones() {
+        let (board, _) = Board::parse(SOURCE);
+        let edit = board
+            .rename(SOURCE, &ItemPath::new(0, 0, 0), "renamed")
+            .unwrap();
+
+        let mut stack = UndoStack::new();
+        stack.record(SOURCE, &edit);
+        let source = stack.undo(&edit.apply(SOURCE)).unwrap();
+        assert!(stack.can_redo());
+
+        stack.record(&source, &edit);
+        assert!(!stack.can_redo());
+    }
+
+    #[test]
+    fn changed_sources_are_not_undone() {
+        let (board, _) = Board::parse(SOURCE);
+        let edit = board
+            .rename(SOURCE, &ItemPath::new(0, 0, 1), "renamed")
+            .unwrap();
+
+        let mut stack = UndoStack::new();
+        stack.record(SOURCE, &edit);
+        let changed = edit.apply(SOURCE).replace("renamed", "changed elsewhere");
+
+        assert_eq!(stack.undo(&changed), None);
+        assert!(
+            stack.can_undo(),
+            "the edit can still be undone on the right source"
+        );
+    }
+
+    #[test]
+    fn shifted_sources_are_not_undone() {
+        let source = "- [ ] a\n- [ ] b\n";
+        let (board, _) = Board::parse(source);
+        let edit = board
+            .edit(
+                source,
+                &Operation::SetChecked {
+                    item: ItemPath::new(0, 0, 0),
+                    checked: true,
+                },
+            )
+            .unwrap();
+
+        let mut stack = UndoStack::new();
+        stack.record(source, &edit);
+        assert_eq!(edit.apply(source), "- [x] a\n- [ ] b\n");
+
+        // Edited elsewhere: the `x` is still at the same offset, but another line moved there
+        let changed = "xxxxxx\n- [x] a\n- [ ] b\n";
+        assert_eq!(stack.undo(changed), None);
+
+        let source = stack.undo("- [x] a\n- [ ] b\n").unwrap();
+        assert_eq!(stack.redo(changed), None);
+        assert_eq!(stack.redo(&source).unwrap(), "- [x] a\n- [ ] b\n");
+    }
+
+    #[test]
+    fn limit() {
+        let mut stack = UndoStack::new().with_limit(2);
+        let mut source = SOURCE.to_string();
+
+        for text in ["a", "b", "c"] {
+            let (board, _) = Board::parse(&source);
+            let edit = board
+                .rename(&source, &ItemPath::new(0, 0, 0), text)
+                .unwrap();
+            stack.record(&source, &edit);
+            source = edit.apply(&source);
+        }
+
+        source = stack.undo(&source).unwrap();
+        source = stack.undo(&source).unwrap();
+        assert!(source.starts_with("- [ ] a"));
+        assert!(!stack.can_undo());
+    }
+}
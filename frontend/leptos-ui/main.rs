@@ -1,6 +1,6 @@
 use std::{env, fs};
 
-use todomd::Board;
+use todomd::{Board, Outline};
 
 fn dothing() {
     let default_todo;
@@ -15,11 +15,11 @@ fn dothing() {
     let file = env::args().nth(1).unwrap_or(default_todo);
     let markdown = fs::read_to_string(file).unwrap();
 
-    let board = Board::parse(&markdown);
+    let (board, _) = Board::parse(&markdown);
 
     println!("");
 
-    board.print(&markdown, true);
+    print!("{}", board.render(&markdown, &mut Outline::new(true)));
 }
 
 fn main() {
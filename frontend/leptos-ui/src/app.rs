@@ -1,178 +1,267 @@
-use leptos::html::*;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use leptos::ev::{self, KeyboardEvent, SubmitEvent};
+use leptos::prelude::*;
 use leptos::task::spawn_local;
-use leptos::{ev::SubmitEvent, prelude::*};
-use serde::{Deserialize, Serialize};
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
-    async fn invoke(cmd: &str, args: JsValue) -> JsValue;
-}
+use todomd::{ItemPath, ListPath, Operation, Query};
 
-#[derive(Serialize, Deserialize)]
-struct GreetArgs<'a> {
-    name: &'a str,
+use crate::backend::{self, CommandError, Loaded};
+use crate::board::Kanban;
+use crate::history::HistoryPanel;
+use crate::source::SourcePane;
+
+/// The open file and the last error, shared by every component through the context
+#[derive(Clone, Copy)]
+pub struct Session {
+    pub loaded: RwSignal<Option<Arc<Loaded>>>,
+    pub error: RwSignal<Option<String>>,
+    /// The card being dragged
+    pub dragging: RwSignal<Option<ItemPath>>,
+    /// The item under the mouse on the board, highlighted in the source
+    pub hovered: RwSignal<Option<ItemPath>>,
+    /// The item last clicked, on the board or in the source
+    pub selected: RwSignal<Option<ItemPath>>,
+    /// The query of the filter bar. Cards without a matching item are hidden.
+    pub filter: RwSignal<Option<Query>>,
 }
 
-#[component]
-pub fn Test1(max: u32) -> impl IntoView {
-    macro_rules! heading {
-        ($q:expr) => {{
-            $q().style("margin: 0.5em;")
-                .child(format!("1 Level {max}"))
-                .into_any()
-        }};
+impl Session {
+    /// Runs a backend command, showing the board it returns or its error
+    pub fn run(self, command: impl Future<Output = Result<Arc<Loaded>, CommandError>> + 'static) {
+        spawn_local(async move { self.show(command.await) });
     }
 
-    let tag = match max {
-        i if i <= 1 => heading!(h5),
-        i if i <= 2 => heading!(h4),
-        i if i <= 4 => heading!(h3),
-        i if i <= 8 => heading!(h2),
-        _ => heading!(h1),
-    };
-
-    let initial = div()
-        .style("border: 1px solid black; margin: 0.5em;")
-        .child(tag);
+    /// Shows a new board, or an error
+    fn show(self, result: Result<Arc<Loaded>, CommandError>) {
+        match result {
+            Ok(loaded) => {
+                self.follow_selection(&loaded);
+                self.loaded.set(Some(loaded));
+                self.error.set(None);
+            }
+            Err(CommandError::NoBoard) => self.loaded.set(None),
+            Err(e) => self.error.set(Some(e.to_string())),
+        }
+    }
 
-    let mut inner = vec![];
+    /// Keeps the same item selected in a new board, finding it by its id. Paths change when
+    /// items are added or moved before it, in the app or in another editor.
+    fn follow_selection(self, new: &Loaded) {
+        let Some(old) = self.loaded.get_untracked() else {
+            return;
+        };
+        let Some(selected) = self.selected.get_untracked() else {
+            return;
+        };
 
-    for i in 0..max {
-        let mut spans = vec![];
-        for j in 0..(max / 2) {
-            spans.push(span().child((i * j).to_string() + ":"));
-        }
-        let s = div().child(spans).into_any();
+        let found = old
+            .board
+            .item_ref(&old.source, &selected)
+            .and_then(|item| new.board.resolve(&new.source, &item))
+            // Renamed items get another id, but usually stay where they were
+            .or_else(|| new.board.item(&selected).map(|_| selected));
 
-        let half = max / 2;
+        self.selected.set(found);
+        self.hovered.set(None);
+    }
 
-        if half > 0 {
-            let q = Test1(Test1Props::builder().max(half).build()).into_any();
+    pub fn edit(self, operation: Operation) {
+        self.run(backend::apply_edit(operation));
+    }
 
-            inner.push((q, s).into_any())
-        } else {
-            inner.push(s.into_any());
+    /// Moves the card being dragged to `index` of `list`
+    pub fn drop_at(self, list: ListPath, index: usize) {
+        if let Some(item) = self.dragging.get_untracked() {
+            self.dragging.set(None);
+            self.edit(Operation::Move { item, list, index });
         }
     }
-
-    initial.child(inner)
 }
 
 #[component]
-pub fn Test2(max: u32) -> impl IntoView {
-    macro_rules! heading {
-        ($q:expr) => {{
-            $q().style("margin: 0.5em;")
-                .child(format!("2 Level {max}"))
-                .into_any()
-        }};
-    }
+fn OpenForm() -> impl IntoView {
+    let session = expect_context::<Session>();
+    let (path, set_path) = signal(String::new());
 
-    let tag = match max {
-        i if i <= 1 => heading!(h5),
-        i if i <= 2 => heading!(h4),
-        i if i <= 4 => heading!(h3),
-        i if i <= 8 => heading!(h2),
-        _ => heading!(h1),
+    let open = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let path = path.get_untracked();
+        if !path.is_empty() {
+            session.run(backend::open_board(PathBuf::from(path)));
+        }
     };
 
     view! {
-        <div style="border: 1px solid black; margin: 0.5em;">
-            {{ tag }}
-            {move || {
-                (0..max)
-                    .map(|i| {
-                        view! {
-                            <div>
-                                {move || {
-                                    let half = max / 2;
-                                    (half > 0).then(|| view! { <Test2 max=half /> }).into_any()
-                                }}
-                                {move || {
-                                    (0..(max / 2))
-                                        .map(|j| {
-                                            view! { <span>{i * j}:</span> }
-                                        })
-                                        .collect_view()
-                                }}
-                            </div>
-                        }
-                    })
-                    .collect_view()
-            }}
-        </div>
+        <form class="open" on:submit=open>
+            <input
+                placeholder="Path to a TODO.md..."
+                on:input=move |ev| set_path.set(event_target_value(&ev))
+                on:keydown=|ev| ev.stop_propagation()
+            />
+            <button type="submit">"Open"</button>
+        </form>
     }
 }
 
+/// A query that filters the cards, like `open and #backend`. While the query can't be parsed, the
+/// last one that could stays in use, and the error points at its column.
 #[component]
-pub fn App() -> impl IntoView {
-    let (name, set_name) = signal(String::new());
-    let (greet_msg, set_greet_msg) = signal(String::new());
+fn FilterBar() -> impl IntoView {
+    let session = expect_context::<Session>();
+    let error = RwSignal::new(None::<String>);
 
-    let update_name = move |ev| {
-        let v = event_target_value(&ev);
-        set_name.set(v);
+    let on_input = move |query: String| {
+        if query.trim().is_empty() {
+            session.filter.set(None);
+            error.set(None);
+            return;
+        }
+        match Query::parse(&query) {
+            Ok(query) => {
+                session.filter.set(Some(query));
+                error.set(None);
+            }
+            Err(e) => error.set(Some(e.to_string())),
+        }
     };
 
-    let greet = move |ev: SubmitEvent| {
-        ev.prevent_default();
-        spawn_local(async move {
-            let name = name.get_untracked();
-            if name.is_empty() {
-                return;
-            }
+    view! {
+        <div class="filter">
+            <input
+                type="search"
+                placeholder="Filter: open and #backend and due < 2026-11-01"
+                class:invalid=move || error.with(Option::is_some)
+                title=move || error.get()
+                on:input=move |ev| on_input(event_target_value(&ev))
+                on:keydown=|ev| ev.stop_propagation()
+            />
+            {move || error.get().map(|e| view! { <span class="filter-error">{e}</span> })}
+        </div>
+    }
+}
 
-            let args = serde_wasm_bindgen::to_value(&GreetArgs { name: &name }).unwrap();
-            // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-            let new_msg = invoke("greet", args).await.as_string().unwrap();
-            set_greet_msg.set(new_msg);
-        });
+/// Edits that were dropped because the file was changed by another program
+#[component]
+fn Conflicts() -> impl IntoView {
+    let session = expect_context::<Session>();
+    let conflicts = move || {
+        session
+            .loaded
+            .with(|l| l.as_ref().map(|l| l.conflicts.clone()).unwrap_or_default())
     };
 
-    let (num, set_num) = signal(None);
+    view! {
+        <Show when=move || !conflicts().is_empty()>
+            <div class="conflicts">
+                <p>"The file was changed elsewhere, and these unsaved edits were dropped:"</p>
+                <ul>
+                    {move || {
+                        conflicts()
+                            .into_iter()
+                            .map(|conflict| view! { <li>{conflict}</li> })
+                            .collect_view()
+                    }}
+                </ul>
+                <button on:click=move |_| {
+                    session.run(backend::dismiss_conflicts())
+                }>"Dismiss"</button>
+            </div>
+        </Show>
+    }
+}
+
+#[component]
+pub fn App() -> impl IntoView {
+    let session = Session {
+        loaded: RwSignal::new(None),
+        error: RwSignal::new(None),
+        dragging: RwSignal::new(None),
+        hovered: RwSignal::new(None),
+        selected: RwSignal::new(None),
+        filter: RwSignal::new(None),
+    };
+    let show_source = RwSignal::new(false);
+    let show_history = RwSignal::new(false);
+    provide_context(session);
 
+    session.run(backend::get_board());
     spawn_local(async move {
-        let n = invoke("get_num", JsValue::NULL).await.as_f64().unwrap() as u32;
+        if let Err(e) = backend::on_board_changed(move |result| session.show(result)).await {
+            session.error.set(Some(e.to_string()));
+        }
+    });
 
-        set_num.set(Some(n));
+    // Ctrl+Z and Ctrl+Shift+Z (or Cmd on macOS) undo and redo edits of the board. Text boxes
+    // keep these keys to themselves.
+    let shortcuts = window_event_listener(ev::keydown, move |ev: KeyboardEvent| {
+        if !(ev.ctrl_key() || ev.meta_key()) || !ev.key().eq_ignore_ascii_case("z") {
+            return;
+        }
+        ev.prevent_default();
+        if ev.shift_key() {
+            session.run(backend::redo());
+        } else {
+            session.run(backend::undo());
+        }
     });
+    on_cleanup(move || shortcuts.remove());
+
+    let path = move || session.loaded.with(|l| l.as_ref().map(|l| l.path.clone()));
+    let dirty = move || session.loaded.with(|l| l.as_ref().is_some_and(|l| l.dirty));
+    let warnings = move || {
+        session
+            .loaded
+            .with(|l| l.as_ref().map_or(0, |l| l.diagnostics.len()))
+    };
 
     view! {
-        <main class="container">
-            <h1>"Welcome to Tauri + Leptos"</h1>
-
-            <div class="row">
-                <a href="https://tauri.app" target="_blank">
-                    <img src="public/tauri.svg" class="logo tauri" alt="Tauri logo" />
-                </a>
-                <a href="https://docs.rs/leptos/" target="_blank">
-                    <img src="public/leptos.svg" class="logo leptos" alt="Leptos logo" />
-                </a>
-            </div>
-            <p>"Click on the Tauri and Leptos logos to learn more."</p>
-
-            <form class="row" on:submit=greet>
-                <input id="greet-input" placeholder="Enter a name..." on:input=update_name />
-                <button type="submit">"Greet"</button>
-            </form>
-            <p>{move || greet_msg.get()}</p>
-
-            {move || {
-                num.get()
-                    .map(|n| {
-                        view! {
-                            <div style="display: flex">
-                                <div>
-                                    <Test1 max=n />
-                                </div>
-                                <div>
-                                    <Test2 max=n />
-                                </div>
-                            </div>
-                        }
-                    })
+        <main class="app">
+            <header class="toolbar">
+                <span class="path">{path}</span>
+                <Show when=dirty>
+                    <span class="dirty">"unsaved"</span>
+                </Show>
+                <Show when={move || warnings() > 0}>
+                    <span class="warnings">{move || format!("{} warnings", warnings())}</span>
+                </Show>
+                <FilterBar />
+                <button on:click=move |_| session.run(backend::save())>"Save"</button>
+                <button on:click=move |_| session.run(backend::reload())>"Reload"</button>
+                <button
+                    class:active=show_source
+                    on:click=move |_| show_source.update(|show| *show = !*show)
+                >
+                    "Source"
+                </button>
+                <button
+                    class:active=show_history
+                    on:click=move |_| show_history.update(|show| *show = !*show)
+                >
+                    "History"
+                </button>
+            </header>
+
+            {move || session.error.get().map(|e| view! { <p class="error">{e}</p> })}
+            <Conflicts />
+
+            {move || match session.loaded.get() {
+                Some(loaded) => {
+                    view! {
+                        <div class="split">
+                            <Kanban loaded=loaded.clone() />
+                            <Show when=move || show_source.get()>
+                                <SourcePane loaded=loaded.clone() />
+                            </Show>
+                            <Show when=move || show_history.get()>
+                                <HistoryPanel />
+                            </Show>
+                        </div>
+                    }
+                        .into_any()
+                }
+                None => view! { <OpenForm /> }.into_any(),
             }}
         </main>
     }
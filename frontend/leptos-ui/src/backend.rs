@@ -0,0 +1,192 @@
+//! Calls to the Tauri commands of `backend/tauri-bin/src/commands.rs`
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use todomd::history::Snapshot;
+use todomd::{Board, Diagnostic, EditError, Operation};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], catch)]
+    async fn invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"], catch)]
+    async fn listen(event: &str, handler: &Closure<dyn FnMut(JsValue)>)
+    -> Result<JsValue, JsValue>;
+}
+
+/// The event the backend sends when the open file was changed by another program, from
+/// `backend/tauri-bin/src/watch.rs`
+const BOARD_CHANGED: &str = "board-changed";
+
+/// What the backend sends after each command
+#[derive(Deserialize)]
+struct BoardView {
+    path: String,
+    source: String,
+    document: serde_json::Value,
+    dirty: bool,
+    diagnostics: Vec<Diagnostic>,
+    conflicts: Vec<String>,
+}
+
+/// A file opened in the backend, and its board
+pub struct Loaded {
+    pub path: String,
+    pub source: String,
+    pub board: Board,
+    /// Whether there are edits that were not saved yet
+    pub dirty: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Edits that were dropped because the file changed under them
+    pub conflicts: Vec<String>,
+}
+
+/// The errors of the backend commands, plus the ways calling them can fail
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum CommandError {
+    NoBoard,
+    Io {
+        path: String,
+        message: String,
+    },
+    Edit(EditError),
+    /// The command couldn't be called, or its answer couldn't be read
+    #[serde(skip)]
+    Invoke(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NoBoard => write!(f, "no file is open"),
+            CommandError::Io { path, message } => write!(f, "{path}: {message}"),
+            CommandError::Edit(e) => write!(f, "{e}"),
+            CommandError::Invoke(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+fn invoke_error(e: impl fmt::Display) -> CommandError {
+    CommandError::Invoke(e.to_string())
+}
+
+/// Calls a command that answers with something else than a board
+async fn call_for<T: DeserializeOwned>(cmd: &str, args: impl Serialize) -> Result<T, CommandError> {
+    let args = args
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(invoke_error)?;
+
+    let value = invoke(cmd, args).await.map_err(|e| {
+        serde_wasm_bindgen::from_value(e.clone())
+            .unwrap_or_else(|_| invoke_error(format!("{cmd} failed: {e:?}")))
+    })?;
+
+    serde_wasm_bindgen::from_value(value).map_err(invoke_error)
+}
+
+async fn call(cmd: &str, args: impl Serialize) -> Result<Arc<Loaded>, CommandError> {
+    loaded(call_for(cmd, args).await?)
+}
+
+fn loaded(view: BoardView) -> Result<Arc<Loaded>, CommandError> {
+    let board = Board::from_json_value(view.document).map_err(invoke_error)?;
+
+    Ok(Arc::new(Loaded {
+        path: view.path,
+        source: view.source,
+        board,
+        dirty: view.dirty,
+        diagnostics: view.diagnostics,
+        conflicts: view.conflicts,
+    }))
+}
+
+/// What Tauri passes to event handlers
+#[derive(Deserialize)]
+struct Event<T> {
+    payload: T,
+}
+
+/// Calls `handler` with the new board each time the open file is changed by another program
+pub async fn on_board_changed(
+    handler: impl Fn(Result<Arc<Loaded>, CommandError>) + 'static,
+) -> Result<(), CommandError> {
+    let closure = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let event = serde_wasm_bindgen::from_value::<Event<BoardView>>(event).map_err(invoke_error);
+        handler(event.and_then(|event| loaded(event.payload)));
+    });
+
+    listen(BOARD_CHANGED, &closure)
+        .await
+        .map_err(|e| invoke_error(format!("can't listen to {BOARD_CHANGED}: {e:?}")))?;
+    // The board is listened to for as long as the app runs
+    closure.forget();
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct NoArgs {}
+
+#[derive(Serialize)]
+struct OpenArgs {
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct RestoreArgs {
+    id: String,
+}
+
+#[derive(Serialize)]
+struct EditArgs {
+    edit: Operation,
+}
+
+pub async fn open_board(path: PathBuf) -> Result<Arc<Loaded>, CommandError> {
+    call("open_board", OpenArgs { path }).await
+}
+
+pub async fn get_board() -> Result<Arc<Loaded>, CommandError> {
+    call("get_board", NoArgs {}).await
+}
+
+pub async fn apply_edit(edit: Operation) -> Result<Arc<Loaded>, CommandError> {
+    call("apply_edit", EditArgs { edit }).await
+}
+
+pub async fn undo() -> Result<Arc<Loaded>, CommandError> {
+    call("undo", NoArgs {}).await
+}
+
+pub async fn redo() -> Result<Arc<Loaded>, CommandError> {
+    call("redo", NoArgs {}).await
+}
+
+pub async fn save() -> Result<Arc<Loaded>, CommandError> {
+    call("save", NoArgs {}).await
+}
+
+pub async fn reload() -> Result<Arc<Loaded>, CommandError> {
+    call("reload", NoArgs {}).await
+}
+
+pub async fn dismiss_conflicts() -> Result<Arc<Loaded>, CommandError> {
+    call("dismiss_conflicts", NoArgs {}).await
+}
+
+/// The saved versions of the open file, newest first
+pub async fn history_list() -> Result<Vec<Snapshot>, CommandError> {
+    call_for("history_list", NoArgs {}).await
+}
+
+pub async fn history_restore(id: String) -> Result<Arc<Loaded>, CommandError> {
+    call("history_restore", RestoreArgs { id }).await
+}
@@ -0,0 +1,351 @@
+//! The kanban view of a board: a column for each list, a card for each item, and the nested list
+//! of an item as a checklist on its card.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use leptos::ev::{DragEvent, KeyboardEvent, MouseEvent};
+use leptos::prelude::*;
+use todomd::{Item, ItemPath, ListPath, Operation, Span};
+
+use crate::app::Session;
+use crate::backend::Loaded;
+use crate::source::covers;
+
+/// The items that match the filter of the session, or `None` without a filter
+#[derive(Clone, Copy)]
+struct Matches(Memo<Option<HashSet<ItemPath>>>);
+
+impl Matches {
+    /// Whether `path` is an item that matches the filter
+    fn contains(self, path: &ItemPath) -> bool {
+        self.0
+            .with(|m| m.as_ref().is_some_and(|m| m.contains(path)))
+    }
+
+    /// Whether the filter hides the card of `path`: none of its items match
+    fn hides(self, path: &ItemPath) -> bool {
+        self.0.with(|m| {
+            m.as_ref()
+                .is_some_and(|m| !m.iter().any(|p| covers(path, p)))
+        })
+    }
+}
+
+/// The columns of the board: every top-level list, titled by its section
+fn columns(loaded: &Loaded) -> Vec<(String, ListPath)> {
+    let mut columns = vec![];
+
+    for (s, section) in loaded.board.sections.iter().enumerate() {
+        let title = section.title(&loaded.source).unwrap_or("Untitled").trim();
+
+        for l in 0..section.element.lists.len() {
+            let title = match l {
+                0 => title.to_string(),
+                _ => format!("{title} ({})", l + 1),
+            };
+            columns.push((title, ListPath::new(s, l)));
+        }
+    }
+
+    columns
+}
+
+/// Small labels for the metadata of an item
+fn chips(item: &Span<Item>) -> Vec<(&'static str, String)> {
+    let metadata = &item.element.metadata;
+    let mut chips = vec![];
+
+    if let Some(priority) = &metadata.priority {
+        chips.push(("priority", format!("!{}", priority.element)));
+    }
+    if let Some(due) = &metadata.due {
+        chips.push(("due", format!("due {}", due.element)));
+    }
+    for assignee in &metadata.assignees {
+        chips.push(("assignee", format!("@{}", assignee.element)));
+    }
+    for tag in &metadata.tags {
+        chips.push(("tag", format!("#{}", tag.element)));
+    }
+
+    chips
+}
+
+#[component]
+fn Checkbox(path: ItemPath, checked: bool) -> impl IntoView {
+    let session = expect_context::<Session>();
+
+    view! {
+        <input
+            type="checkbox"
+            prop:checked=checked
+            on:change=move |ev| {
+                session
+                    .edit(Operation::SetChecked {
+                        item: path.clone(),
+                        checked: event_target_checked(&ev),
+                    })
+            }
+        />
+    }
+}
+
+/// The text of a card. Double-clicking it opens an edit box, which replaces only the parts of the
+/// text that were changed. Enter or leaving the box saves, Escape cancels, Shift+Enter adds a line.
+#[component]
+fn Title(path: ItemPath, text: String, editable: String) -> impl IntoView {
+    let session = expect_context::<Session>();
+    let editing = RwSignal::new(false);
+    let input = NodeRef::<leptos::html::Textarea>::new();
+
+    Effect::new(move || {
+        if let Some(input) = input.get() {
+            let _ = input.focus();
+        }
+    });
+
+    let original = editable.clone();
+    let save = move |text: String| {
+        if editing.get_untracked() {
+            editing.set(false);
+            if text != original {
+                session.edit(Operation::ReplaceText {
+                    item: path.clone(),
+                    text,
+                });
+            }
+        }
+    };
+    let save_on_blur = save.clone();
+
+    let on_keydown = move |ev: KeyboardEvent| {
+        // Keys like Ctrl+Z act on the text being edited, not on the board
+        ev.stop_propagation();
+
+        match ev.key().as_str() {
+            "Enter" if !ev.shift_key() => {
+                ev.prevent_default();
+                save(event_target_value(&ev));
+            }
+            "Escape" => editing.set(false),
+            _ => {}
+        }
+    };
+
+    view! {
+        <Show
+            when=move || editing.get()
+            fallback=move || {
+                view! {
+                    <span class="title" on:dblclick=move |_| editing.set(true)>
+                        {text.clone()}
+                    </span>
+                }
+            }
+        >
+            <textarea
+                class="title-edit"
+                node_ref=input
+                prop:value=editable.clone()
+                on:keydown=on_keydown.clone()
+                on:blur={
+                    let save = save_on_blur.clone();
+                    move |ev| save(event_target_value(&ev))
+                }
+            />
+        </Show>
+    }
+}
+
+#[component]
+fn Subtasks(loaded: Arc<Loaded>, path: ListPath) -> impl IntoView {
+    let session = expect_context::<Session>();
+    let matches = expect_context::<Matches>();
+    let len = loaded
+        .board
+        .list(&path)
+        .map_or(0, |l| l.element.items.len());
+
+    let items = (0..len)
+        .map(|i| {
+            let path = path.item(i);
+            let item = loaded.board.item(&path).expect("items of the list exist");
+            let checked = item.element.checkbox.as_ref().map(|c| c.element);
+            let text = item.text(&loaded.source).to_string();
+            let nested = item.element.nested_list.is_some().then(|| {
+                view! { <Subtasks loaded=loaded.clone() path=path.nested() /> }.into_any()
+            });
+
+            let is_selected = {
+                let path = path.clone();
+                move || session.selected.with(|s| s.as_ref() == Some(&path))
+            };
+            let is_match = {
+                let path = path.clone();
+                move || matches.contains(&path)
+            };
+            let select = {
+                let path = path.clone();
+                move |ev: MouseEvent| {
+                    ev.stop_propagation();
+                    session.selected.set(Some(path.clone()));
+                }
+            };
+
+            view! {
+                <li
+                    class:done=checked == Some(true)
+                    class:selected=is_selected
+                    class:match=is_match
+                    on:click=select
+                >
+                    {checked.map(|checked| view! { <Checkbox path=path.clone() checked /> })}
+                    <span>{text}</span>
+                    {nested}
+                </li>
+            }
+        })
+        .collect_view();
+
+    view! { <ul class="subtasks">{items}</ul> }
+}
+
+#[component]
+fn Card(loaded: Arc<Loaded>, path: ItemPath) -> impl IntoView {
+    let item = loaded
+        .board
+        .item(&path)
+        .expect("cards are made from items of the board");
+    let checked = item.element.checkbox.as_ref().map(|c| c.element);
+    let text = item.text(&loaded.source).to_string();
+    let editable = item.editable_text(&loaded.source);
+
+    let chips = chips(item)
+        .into_iter()
+        .map(|(class, text)| view! { <span class=format!("chip {class}")>{text}</span> })
+        .collect_view();
+
+    let subtasks = item
+        .element
+        .nested_list
+        .is_some()
+        .then(|| view! { <Subtasks loaded=loaded.clone() path=path.nested() /> });
+    let checkbox = checked.map(|checked| view! { <Checkbox path=path.clone() checked /> });
+    let title = view! { <Title path=path.clone() text editable /> };
+
+    let session = expect_context::<Session>();
+    let matches = expect_context::<Matches>();
+
+    let is_dragged = {
+        let path = path.clone();
+        move || session.dragging.with(|d| d.as_ref() == Some(&path))
+    };
+    let is_selected = {
+        let path = path.clone();
+        move || {
+            session
+                .selected
+                .with(|s| s.as_ref().is_some_and(|s| covers(&path, s)))
+        }
+    };
+    let is_filtered_out = {
+        let path = path.clone();
+        move || matches.hides(&path)
+    };
+    let hover = {
+        let path = path.clone();
+        move |_: MouseEvent| session.hovered.set(Some(path.clone()))
+    };
+    let select = {
+        let path = path.clone();
+        move |_: MouseEvent| session.selected.set(Some(path.clone()))
+    };
+    let start_drag = {
+        let path = path.clone();
+        move |_: DragEvent| session.dragging.set(Some(path.clone()))
+    };
+    // Dropping a card on another one puts it right before that one
+    let drop = move |ev: DragEvent| {
+        ev.prevent_default();
+        ev.stop_propagation();
+        session.drop_at(path.list.clone(), path.index);
+    };
+
+    view! {
+        <article
+            class="card"
+            class:done=checked == Some(true)
+            class:dragging=is_dragged
+            class:selected=is_selected
+            class:filtered-out=is_filtered_out
+            draggable="true"
+            on:mouseenter=hover
+            on:mouseleave=move |_| session.hovered.set(None)
+            on:click=select
+            on:dragstart=start_drag
+            on:dragend=move |_| session.dragging.set(None)
+            on:dragover=|ev| ev.prevent_default()
+            on:drop=drop
+        >
+            <div class="card-title">
+                {checkbox}
+                {title}
+            </div>
+            <div class="chips">{chips}</div>
+            {subtasks}
+        </article>
+    }
+}
+
+#[component]
+fn Column(loaded: Arc<Loaded>, title: String, path: ListPath) -> impl IntoView {
+    let len = loaded
+        .board
+        .list(&path)
+        .map_or(0, |l| l.element.items.len());
+
+    let cards = (0..len)
+        .map(|i| view! { <Card loaded=loaded.clone() path=path.item(i) /> })
+        .collect_view();
+
+    let session = expect_context::<Session>();
+
+    // Dropping a card anywhere else in the column puts it at the end
+    view! {
+        <section
+            class="column"
+            on:dragover=|ev| ev.prevent_default()
+            on:drop=move |ev| {
+                ev.prevent_default();
+                session.drop_at(path.clone(), len);
+            }
+        >
+            <h2>{title}</h2>
+            {cards}
+        </section>
+    }
+}
+
+#[component]
+pub fn Kanban(loaded: Arc<Loaded>) -> impl IntoView {
+    let session = expect_context::<Session>();
+    let matches = {
+        let loaded = loaded.clone();
+        Memo::new(move |_| {
+            session.filter.with(|query| {
+                let query = query.as_ref()?;
+                let matches = loaded.board.query(&loaded.source, query);
+                Some(matches.into_iter().map(|m| m.path).collect())
+            })
+        })
+    };
+    provide_context(Matches(matches));
+
+    let columns = columns(&loaded)
+        .into_iter()
+        .map(|(title, path)| view! { <Column loaded=loaded.clone() title path /> })
+        .collect_view();
+
+    view! { <div class="board">{columns}</div> }
+}
@@ -0,0 +1,66 @@
+//! The versions of the file kept at each save, from the `.history` directory next to it. Restoring
+//! one is recorded as a version too, so it can be undone from the same list.
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use todomd::history::Snapshot;
+
+use crate::app::Session;
+use crate::backend;
+
+#[component]
+pub fn HistoryPanel() -> impl IntoView {
+    let session = expect_context::<Session>();
+    let snapshots = RwSignal::new(Vec::<Snapshot>::new());
+
+    let refresh = move || {
+        spawn_local(async move {
+            match backend::history_list().await {
+                Ok(list) => snapshots.set(list),
+                Err(e) => session.error.set(Some(e.to_string())),
+            }
+        })
+    };
+
+    // Saves and restores add versions, so the list is read again each time the board changes
+    Effect::new(move |_| {
+        session.loaded.track();
+        refresh();
+    });
+
+    let rows = move || {
+        snapshots
+            .get()
+            .into_iter()
+            .map(|snapshot| {
+                let time = snapshot.time.format("%Y-%m-%d %H:%M:%S").to_string();
+                let note = snapshot
+                    .restored_from
+                    .map(|from| format!("restored from {from}"));
+                let id = snapshot.id;
+
+                view! {
+                    <li>
+                        <span class="time">{time}</span>
+                        <span class="note">{note}</span>
+                        <button on:click=move |_| {
+                            session.run(backend::history_restore(id.clone()))
+                        }>"Restore"</button>
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <aside class="history">
+            <h2>"History"</h2>
+            <Show
+                when=move || !snapshots.with(Vec::is_empty)
+                fallback=|| view! { <p>"No saved versions yet"</p> }
+            >
+                <ul>{rows}</ul>
+            </Show>
+        </aside>
+    }
+}
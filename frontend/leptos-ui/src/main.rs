@@ -1,4 +1,8 @@
 mod app;
+mod backend;
+mod board;
+mod history;
+mod source;
 
 use app::*;
 use leptos::prelude::*;
@@ -0,0 +1,115 @@
+//! The raw markdown next to the board, with the source ranges of items highlighted. It shows what
+//! the parser made of the file: the bytes of each item, and which of them are its contents.
+
+use std::sync::Arc;
+
+use leptos::prelude::*;
+use todomd::{ItemPath, Range};
+
+use crate::app::Session;
+use crate::backend::Loaded;
+
+/// A run of source bytes that belong to the same item, and are all contents of it or all not
+struct Segment {
+    range: Range,
+    item: Option<ItemPath>,
+    content: bool,
+}
+
+/// Whether `inner` is `outer` or one of its subtasks, at any depth
+pub fn covers(outer: &ItemPath, inner: &ItemPath) -> bool {
+    inner == outer || inner.list.is_inside(outer)
+}
+
+/// Cuts the source at every boundary of an item range or of a contents range
+fn segments(loaded: &Loaded) -> Vec<Segment> {
+    let items = loaded.board.items();
+
+    let mut cuts = vec![0, loaded.source.len()];
+    for (_, item) in &items {
+        cuts.extend([item.range.start, item.range.end]);
+        for range in &item.element.contents {
+            cuts.extend([range.start, range.end]);
+        }
+    }
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut segments: Vec<Segment> = vec![];
+
+    for window in cuts.windows(2) {
+        let range = window[0]..window[1];
+        let item = loaded.board.item_at(range.start);
+        let content = item.as_ref().is_some_and(|path| {
+            let item = loaded
+                .board
+                .item(path)
+                .expect("item_at gives items of the board");
+            item.element
+                .contents
+                .iter()
+                .any(|r| r.contains(&range.start))
+        });
+
+        match segments.last_mut() {
+            Some(last) if last.item == item && last.content == content => {
+                last.range.end = range.end
+            }
+            _ => segments.push(Segment {
+                range,
+                item,
+                content,
+            }),
+        }
+    }
+
+    segments
+}
+
+#[component]
+pub fn SourcePane(loaded: Arc<Loaded>) -> impl IntoView {
+    let session = expect_context::<Session>();
+
+    let spans = segments(&loaded)
+        .into_iter()
+        .map(|segment| {
+            let text = loaded.source[segment.range].to_string();
+
+            let Some(path) = segment.item else {
+                return view! { <span>{text}</span> }.into_any();
+            };
+
+            let hovered = {
+                let path = path.clone();
+                move || {
+                    session
+                        .hovered
+                        .with(|h| h.as_ref().is_some_and(|h| covers(h, &path)))
+                }
+            };
+            let selected = {
+                let path = path.clone();
+                move || {
+                    session
+                        .selected
+                        .with(|s| s.as_ref().is_some_and(|s| covers(s, &path)))
+                }
+            };
+
+            view! {
+                <span
+                    class="item"
+                    class:content=segment.content
+                    class:hovered=hovered
+                    class:selected=selected
+                    on:click=move |_| session.selected.set(Some(path.clone()))
+                >
+                    {text}
+                </span>
+            }
+            .into_any()
+        })
+        .collect_view();
+
+    view! { <pre class="source">{spans}</pre> }
+}